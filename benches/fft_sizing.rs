@@ -0,0 +1,23 @@
+//! Compare FFT length policies on lengths that straddle powers of two
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fft_correlation::{fft_correlate_1d_with_sizing, FftSizing, Mode};
+
+fn sizing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft_sizing");
+    // (signal, template) lengths from just below to just above powers of two
+    for (signal_len, template_len) in [(1025, 64), (1025, 1024), (2000, 49), (4097, 256), (40_000, 1500)] {
+        let signal: Vec<f32> = (0..signal_len).map(|i| (i as f32 * 0.1).sin()).collect();
+        let template: Vec<f32> = (0..template_len).map(|i| (i as f32 * 0.37).cos()).collect();
+        for policy in [FftSizing::PowerOfTwo, FftSizing::Fast] {
+            let id = BenchmarkId::new(format!("{:?}", policy), format!("{}x{}", signal_len, template_len));
+            group.bench_with_input(id, &policy, |b, &policy| {
+                b.iter(|| fft_correlate_1d_with_sizing(black_box(&signal), black_box(&template), Mode::Full, policy))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sizing);
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+//! Locate the template length where the FFT method overtakes the direct method
+//!
+//! `choose_method` switches from Direct to Fft where its cost model says so; the timings here show
+//! where the switch actually pays off on the machine running the benchmark.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fft_correlation::{choose_method, correlate_1d, Method, Mode};
+
+fn crossover(c: &mut Criterion) {
+    let mut group = c.benchmark_group("method_crossover");
+    let signal_len = 4096;
+    let signal: Vec<f32> = (0..signal_len).map(|i| (i as f32 * 0.1).sin()).collect();
+    for template_len in [8, 16, 24, 32, 48, 64, 96, 128, 256] {
+        let template: Vec<f32> = (0..template_len).map(|i| (i as f32 * 0.37).cos()).collect();
+        let chosen = choose_method(signal_len, template_len, Mode::Valid);
+        for method in [Method::Direct, Method::Fft] {
+            let id = BenchmarkId::new(format!("{:?}", method), format!("{}x{} (auto: {:?})", signal_len, template_len, chosen));
+            group.bench_with_input(id, &method, |b, &method| {
+                b.iter(|| correlate_1d(black_box(&signal), black_box(&template), Mode::Valid, method))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, crossover);
+criterion_main!(benches);
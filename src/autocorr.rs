@@ -0,0 +1,197 @@
+//! Autocorrelation from the power spectrum
+//!
+//! `fft_correlate_1d(x, x, Mode::Full)` transforms the same signal twice and returns all
+//! `2N - 1` lags unscaled. [`autocorrelate`] needs a single forward FFT: the inverse transform of the
+//! power spectrum `|X|²` is the autocorrelation. Limiting `max_lag` also shrinks the FFT, since only
+//! `N + max_lag` points are needed to keep the requested lags free of circular wrap-around.
+//! Scaling options follow MATLAB's `xcorr`.
+
+use crate::lag_window::{lag_buffer, max_lag_bound};
+use crate::{plan_fft, FftCorrelationError, FftFloat, FftSizing, Result};
+
+/// Normalization of correlation values, named after MATLAB's `xcorr` scale options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scaling {
+    /// Raw sums of products
+    #[default]
+    None,
+    /// Divide by the signal length `N`
+    Biased,
+    /// Divide each lag `m` by its number of overlapping samples, `N - |m|` for one signal
+    Unbiased,
+    /// Normalize so the zero-lag autocorrelations are 1 (MATLAB's `"normalized"` / `"coeff"`)
+    Coeff,
+}
+
+/// Options for [`autocorrelate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AutocorrOptions {
+    /// Largest lag to return; `None` returns every lag up to `N - 1`
+    pub max_lag: Option<usize>,
+    /// Normalization applied to every lag
+    pub scaling: Scaling,
+    /// Return only lags `0..=max_lag` instead of `-max_lag..=max_lag`
+    pub one_sided: bool,
+}
+
+/// Autocorrelation of `signal` computed from its power spectrum
+///
+/// Returns `2 * max_lag + 1` values for lags `-max_lag..=max_lag` (index `max_lag` is lag 0), or
+/// `max_lag + 1` values for lags `0..=max_lag` when `one_sided` is set. The autocorrelation is
+/// symmetric, so negative lags mirror the positive ones. Lags of `N` or more have no overlap and are
+/// zero. With [`Scaling::Coeff`], an all-zero signal yields all zeros.
+///
+/// Returns an empty vector if `signal` is empty.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidLagRange` if `max_lag` exceeds `isize::MAX` or the
+/// requested lags cannot be allocated, and `FftCorrelationError::FftProcessing` if FFT processing
+/// fails.
+///
+/// # References
+///
+/// - MATLAB xcorr: https://www.mathworks.com/help/signal/ref/xcorr.html
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{autocorrelate, AutocorrOptions, Scaling};
+///
+/// let signal = [1.0f64, 2.0, 3.0];
+/// let options = AutocorrOptions { scaling: Scaling::Coeff, ..Default::default() };
+/// let acf = autocorrelate(&signal, &options).unwrap();
+/// let expected = [3.0 / 14.0, 8.0 / 14.0, 1.0, 8.0 / 14.0, 3.0 / 14.0];
+/// for (a, b) in acf.iter().zip(expected.iter()) {
+///     assert!((a - b).abs() < 1e-12);
+/// }
+/// ```
+pub fn autocorrelate<T: FftFloat>(signal: &[T], options: &AutocorrOptions) -> Result<Vec<T>> {
+    if signal.is_empty() {
+        return Ok(Vec::new());
+    }
+    let n = signal.len();
+    let max_lag = options.max_lag.unwrap_or(n - 1);
+    let bound = max_lag_bound(max_lag)?;
+    let mut output = lag_buffer(if options.one_sided { 0 } else { -bound }, bound)?;
+    let computed_lags = max_lag.min(n - 1);
+
+    // Lags up to computed_lags do not wrap as long as fft_size >= n + computed_lags
+    let fft_size = FftSizing::default().fft_len(n + computed_lags);
+    let (r2c, c2r) = plan_fft(fft_size);
+    let mut padded = vec![T::zero(); fft_size];
+    padded[..n].copy_from_slice(signal);
+    let mut spectrum = r2c.make_output_vec();
+    r2c.process(&mut padded, &mut spectrum)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for signal: {:?}", e)))?;
+
+    for bin in spectrum.iter_mut() {
+        *bin = bin.norm_sqr().into();
+    }
+    c2r.process(&mut spectrum, &mut padded)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+
+    let normalization = T::from_len(fft_size);
+    let zero_lag = padded[0] / normalization;
+    let value_at = |lag: usize| {
+        if lag > computed_lags {
+            return T::zero();
+        }
+        let value = padded[lag] / normalization;
+        match options.scaling {
+            Scaling::None => value,
+            Scaling::Biased => value / T::from_len(n),
+            Scaling::Unbiased => value / T::from_len(n - lag),
+            Scaling::Coeff if zero_lag > T::zero() => value / zero_lag,
+            Scaling::Coeff => T::zero(),
+        }
+    };
+
+    // Negative lags mirror the positive ones
+    if !options.one_sided {
+        output.extend((1..=max_lag).rev().map(value_at));
+    }
+    output.extend((0..=max_lag).map(value_at));
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_correlate_1d, Mode};
+
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| ((i as f64) * 0.41).sin() + 0.3 * ((i as f64) * 1.7).cos() + 0.05 * i as f64).collect()
+    }
+
+    #[test]
+    fn test_autocorrelate_matches_full_correlation() {
+        for len in 1..=40 {
+            let signal = test_signal(len);
+            let full = fft_correlate_1d(&signal, &signal, Mode::Full).unwrap();
+            let acf = autocorrelate(&signal, &AutocorrOptions::default()).unwrap();
+            assert_eq!(acf.len(), full.len());
+            for (a, b) in acf.iter().zip(full.iter()) {
+                assert!((a - b).abs() < 1e-10, "Length {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_autocorrelate_scalings() {
+        let signal = test_signal(25);
+        let raw = autocorrelate(&signal, &AutocorrOptions { one_sided: true, ..Default::default() }).unwrap();
+        let scaled = |scaling| {
+            autocorrelate(&signal, &AutocorrOptions { scaling, one_sided: true, ..Default::default() }).unwrap()
+        };
+        let (biased, unbiased, coeff) = (scaled(Scaling::Biased), scaled(Scaling::Unbiased), scaled(Scaling::Coeff));
+        for lag in 0..25 {
+            assert!((biased[lag] - raw[lag] / 25.0).abs() < 1e-12);
+            assert!((unbiased[lag] - raw[lag] / (25 - lag) as f64).abs() < 1e-12);
+            assert!((coeff[lag] - raw[lag] / raw[0]).abs() < 1e-12);
+        }
+        assert!((coeff[0] - 1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_autocorrelate_max_lag_and_sidedness() {
+        let signal = test_signal(100);
+        let all = autocorrelate(&signal, &AutocorrOptions { one_sided: true, ..Default::default() }).unwrap();
+
+        let options = AutocorrOptions { max_lag: Some(5), scaling: Scaling::Unbiased, one_sided: false };
+        let windowed = autocorrelate(&signal, &options).unwrap();
+        assert_eq!(windowed.len(), 11);
+        for (index, &value) in windowed.iter().enumerate() {
+            let lag = (index as isize - 5).unsigned_abs();
+            assert!((value - all[lag] / (100 - lag) as f64).abs() < 1e-10, "Lag {}", lag);
+        }
+
+        // Lags beyond the signal length are zero, as in MATLAB
+        let short = [1.0f32, -1.0];
+        let padded = autocorrelate(&short, &AutocorrOptions { max_lag: Some(3), one_sided: true, ..Default::default() }).unwrap();
+        assert_eq!(padded.len(), 4);
+        assert!((padded[0] - 2.0).abs() < 1e-6 && (padded[1] + 1.0).abs() < 1e-6);
+        assert_eq!(&padded[2..], &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_autocorrelate_degenerate_inputs() {
+        assert!(autocorrelate::<f32>(&[], &AutocorrOptions::default()).unwrap().is_empty());
+        let zeros = autocorrelate(&[0.0f64; 8], &AutocorrOptions { scaling: Scaling::Coeff, ..Default::default() }).unwrap();
+        assert_eq!(zeros, vec![0.0; 15]);
+    }
+
+    #[test]
+    fn test_autocorrelate_rejects_unallocatable_max_lag() {
+        let signal = [1.0f64, 2.0, 3.0];
+        for max_lag in [usize::MAX, isize::MAX as usize, usize::MAX / 4] {
+            for one_sided in [false, true] {
+                let options = AutocorrOptions { max_lag: Some(max_lag), one_sided, ..Default::default() };
+                assert!(matches!(
+                    autocorrelate(&signal, &options),
+                    Err(FftCorrelationError::InvalidLagRange { .. })
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Batched correlation over many independent signal/template pairs
+//!
+//! With the `rayon` cargo feature enabled, pairs are correlated in parallel on the rayon thread
+//! pool; each worker thread uses its own thread-local FFT planner. Without the feature the same
+//! API runs sequentially, so callers do not need to change code when toggling parallelism.
+//!
+//! Output order always matches input order, and each pair reports its own `Result`, so one
+//! failing pair does not discard the others.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{fft_correlate_1d, FftCorrelationError, FftFloat, Mode, Result};
+
+/// Correlate `signals[i]` against `templates[i]` for every `i`
+///
+/// Each item is computed exactly as `fft_correlate_1d(signals[i], templates[i], mode)`, and the
+/// returned vector holds the per-pair results in input order.
+///
+/// # Errors
+///
+/// If `signals` and `templates` have different lengths, the result still has one item per entry
+/// of the longer list: entries without a partner report `FftCorrelationError::ShapeMismatch` with
+/// the number of signals as `expected` and the number of templates as `actual`. Every other item
+/// reports the errors of [`fft_correlate_1d`].
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_batch, Mode};
+///
+/// let signals = vec![vec![1.0f32, 2.0, 3.0, 4.0], vec![0.0, 1.0, 0.0]];
+/// let templates = vec![vec![1.0f32, 1.0], vec![1.0]];
+/// let results = fft_correlate_batch(&signals, &templates, Mode::Valid);
+/// assert_eq!(results[0].as_ref().unwrap().len(), 3);
+/// assert_eq!(results[1].as_ref().unwrap().len(), 3);
+/// ```
+pub fn fft_correlate_batch<T, S, U>(signals: &[S], templates: &[U], mode: Mode) -> Vec<Result<Vec<T>>>
+where
+    T: FftFloat,
+    S: AsRef<[T]> + Sync,
+    U: AsRef<[T]> + Sync,
+{
+    #[cfg(feature = "rayon")]
+    let pairs = signals.par_iter().zip(templates.par_iter());
+    #[cfg(not(feature = "rayon"))]
+    let pairs = signals.iter().zip(templates.iter());
+
+    let mut results: Vec<_> = pairs
+        .map(|(signal, template)| fft_correlate_1d(signal.as_ref(), template.as_ref(), mode))
+        .collect();
+    let unpaired = signals.len().abs_diff(templates.len());
+    results.extend((0..unpaired).map(|_| {
+        Err(FftCorrelationError::ShapeMismatch { expected: signals.len(), actual: templates.len() })
+    }));
+    results
+}
+
+/// Correlate many signals against one shared template
+///
+/// Equivalent to [`fft_correlate_batch`] with the same template repeated for every signal.
+pub fn fft_correlate_batch_template<T, S>(signals: &[S], template: &[T], mode: Mode) -> Vec<Result<Vec<T>>>
+where
+    T: FftFloat,
+    S: AsRef<[T]> + Sync,
+{
+    #[cfg(feature = "rayon")]
+    let signals_iter = signals.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let signals_iter = signals.iter();
+
+    signals_iter
+        .map(|signal| fft_correlate_1d(signal.as_ref(), template, mode))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_pairs(count: usize) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let signals = (0..count)
+            .map(|k| (0..50 + 13 * k).map(|i| ((i * (k + 1)) as f64 * 0.05).sin()).collect())
+            .collect();
+        let templates = (0..count)
+            .map(|k| (0..1 + k % 9).map(|i| ((i + k) as f64 * 0.3).cos()).collect())
+            .collect();
+        (signals, templates)
+    }
+
+    #[test]
+    fn test_batch_matches_individual_calls_in_order() {
+        let (signals, templates) = make_pairs(40);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let results = fft_correlate_batch(&signals, &templates, mode);
+            assert_eq!(results.len(), signals.len());
+            for (i, result) in results.into_iter().enumerate() {
+                let expected = fft_correlate_1d(&signals[i], &templates[i], mode).unwrap();
+                assert_eq!(result.unwrap(), expected, "Pair {} out of order or mismatched", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_accepts_slices_and_empty_items() {
+        let signals: Vec<&[f32]> = vec![&[1.0, 2.0, 3.0], &[], &[4.0]];
+        let templates: Vec<&[f32]> = vec![&[1.0], &[1.0], &[]];
+        let results = fft_correlate_batch(&signals, &templates, Mode::Full);
+        assert_eq!(results[0].as_ref().unwrap().len(), 3);
+        assert!(results[1].as_ref().unwrap().is_empty());
+        assert!(results[2].as_ref().unwrap().is_empty());
+
+        let none: Vec<Result<Vec<f32>>> = fft_correlate_batch::<f32, Vec<f32>, Vec<f32>>(&[], &[], Mode::Full);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_batch_length_mismatch_reports_unpaired_items() {
+        let signals = vec![vec![1.0f32; 4]; 2];
+        let templates = vec![vec![1.0f32; 2]; 3];
+        let results = fft_correlate_batch(&signals, &templates, Mode::Full);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().len(), 5);
+        assert_eq!(results[1].as_ref().unwrap().len(), 5);
+        assert!(matches!(results[2], Err(FftCorrelationError::ShapeMismatch { expected: 2, actual: 3 })));
+
+        let results = fft_correlate_batch(&signals, &templates[..1], Mode::Full);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(FftCorrelationError::ShapeMismatch { expected: 2, actual: 1 })));
+    }
+
+    #[test]
+    fn test_batch_shared_template() {
+        let (signals, templates) = make_pairs(16);
+        let template = &templates[5];
+        let results = fft_correlate_batch_template(&signals, template, Mode::Same);
+        for (signal, result) in signals.iter().zip(results) {
+            assert_eq!(result.unwrap(), fft_correlate_1d(signal, template, Mode::Same).unwrap());
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! FFT-based linear convolution matching `scipy.signal.fftconvolve`
+//!
+//! Convolution shares all of the correlation machinery except the template reversal:
+//! `convolve(x, h)[k] = sum_i x[k - i] * h[i]`.
+//!
+//! Mode conventions follow scipy for convolution, which differ from correlation in one respect:
+//! since convolution is commutative, Valid mode does not care which input is longer and returns
+//! `max(N, M) - min(N, M) + 1` samples, whereas [`fft_correlate_1d`](crate::fft_correlate_1d)
+//! returns an empty Valid output when the template is longer than the signal. Full and Same mode
+//! slice the Full result exactly as for correlation (Same is centered on `signal.len()` samples).
+
+use crate::{inverse_to_output, padded_spectrum, plan_fft, FftFloat, FftSizing, Mode, Result};
+
+/// Convolve two 1D signals using FFT
+///
+/// - `Mode::Full`: Returns the complete convolution (signal.len() + kernel.len() - 1)
+/// - `Mode::Same`: Returns output of signal.len() samples, centered with respect to Full
+/// - `Mode::Valid`: Returns the samples that do not depend on zero padding,
+///   `max(N, M) - min(N, M) + 1` samples regardless of which input is longer
+///
+/// Returns an empty vector if either input is empty.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # References
+///
+/// - scipy.signal.fftconvolve: https://docs.scipy.org/doc/scipy/reference/generated/scipy.signal.fftconvolve.html
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_convolve_1d, Mode};
+///
+/// let full = fft_convolve_1d(&[1.0f64, 2.0, 3.0], &[0.0, 1.0, 0.5], Mode::Full).unwrap();
+/// let expected = [0.0, 1.0, 2.5, 4.0, 1.5];
+/// for (a, b) in full.iter().zip(expected.iter()) {
+///     assert!((a - b).abs() < 1e-12);
+/// }
+/// ```
+pub fn fft_convolve_1d<T: FftFloat>(signal: &[T], kernel: &[T], mode: Mode) -> Result<Vec<T>> {
+    if signal.is_empty() || kernel.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output_len = signal.len() + kernel.len() - 1;
+    let fft_size = FftSizing::default().fft_len(output_len);
+    let (r2c, c2r) = plan_fft(fft_size);
+
+    let mut product = padded_spectrum(signal, fft_size, r2c.as_ref())?;
+    let kernel_spectrum = padded_spectrum(kernel, fft_size, r2c.as_ref())?;
+    for (s, k) in product.iter_mut().zip(kernel_spectrum.iter()) {
+        *s *= k;
+    }
+
+    let full = inverse_to_output(product, fft_size, c2r.as_ref(), signal.len(), kernel.len(), Mode::Full)?;
+    Ok(match mode {
+        Mode::Full => full,
+        Mode::Same => {
+            let start = (output_len - signal.len()) / 2;
+            full[start..start + signal.len()].to_vec()
+        }
+        Mode::Valid => {
+            let shorter = signal.len().min(kernel.len());
+            let valid_len = signal.len().max(kernel.len()) - shorter + 1;
+            full[shorter - 1..shorter - 1 + valid_len].to_vec()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Naive linear convolution used for correctness checks.
+    fn naive_full_convolution(signal: &[f64], kernel: &[f64]) -> Vec<f64> {
+        let mut result = vec![0.0; signal.len() + kernel.len() - 1];
+        for (i, &s) in signal.iter().enumerate() {
+            for (j, &k) in kernel.iter().enumerate() {
+                result[i + j] += s * k;
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_fft_convolve_matches_naive_across_modes() {
+        for signal_len in 1..=9 {
+            for kernel_len in 1..=9 {
+                let signal: Vec<f64> = (0..signal_len).map(|i| ((i as f64) * 0.7).cos() + (i as f64) * 0.05).collect();
+                let kernel: Vec<f64> = (0..kernel_len).map(|i| ((i as f64) * 0.4).sin() - (i as f64) * 0.03).collect();
+                let naive = naive_full_convolution(&signal, &kernel);
+
+                let full = fft_convolve_1d(&signal, &kernel, Mode::Full).unwrap();
+                assert_eq!(full.len(), naive.len());
+                for (a, b) in full.iter().zip(naive.iter()) {
+                    assert!((a - b).abs() < 1e-12, "Full mismatch for {} x {}", signal_len, kernel_len);
+                }
+
+                let same = fft_convolve_1d(&signal, &kernel, Mode::Same).unwrap();
+                let start = (naive.len() - signal_len) / 2;
+                assert_eq!(same.len(), signal_len);
+                for (a, b) in same.iter().zip(naive[start..].iter()) {
+                    assert!((a - b).abs() < 1e-12, "Same mismatch for {} x {}", signal_len, kernel_len);
+                }
+
+                let valid = fft_convolve_1d(&signal, &kernel, Mode::Valid).unwrap();
+                let shorter = signal_len.min(kernel_len);
+                let valid_len = signal_len.max(kernel_len) - shorter + 1;
+                assert_eq!(valid.len(), valid_len, "Valid length for {} x {}", signal_len, kernel_len);
+                for (a, b) in valid.iter().zip(naive[shorter - 1..].iter()) {
+                    assert!((a - b).abs() < 1e-12, "Valid mismatch for {} x {}", signal_len, kernel_len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_convolve_scipy_reference_values() {
+        // scipy.signal.fftconvolve([1, 2, 3, 4], [1, 1, 0.5], mode)
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+        let kernel: Vec<f32> = vec![1.0, 1.0, 0.5];
+        let cases: [(Mode, &[f32]); 3] = [
+            (Mode::Full, &[1.0, 3.0, 5.5, 8.0, 5.5, 2.0]),
+            (Mode::Same, &[3.0, 5.5, 8.0, 5.5]),
+            (Mode::Valid, &[5.5, 8.0]),
+        ];
+        for (mode, expected) in cases {
+            let result = fft_convolve_1d(&signal, &kernel, mode).unwrap();
+            assert_eq!(result.len(), expected.len(), "{:?}", mode);
+            for (a, b) in result.iter().zip(expected.iter()) {
+                assert!((a - b).abs() < 1e-5, "{:?}: {} vs {}", mode, a, b);
+            }
+        }
+
+        // Valid swaps inputs when the kernel is longer; Same keeps the first input's length
+        let valid = fft_convolve_1d(&kernel, &signal, Mode::Valid).unwrap();
+        assert_eq!(valid.len(), 2);
+        assert!((valid[0] - 5.5).abs() < 1e-5 && (valid[1] - 8.0).abs() < 1e-5);
+        let same = fft_convolve_1d(&kernel, &signal, Mode::Same).unwrap();
+        assert_eq!(same.len(), 3);
+        for (a, b) in same.iter().zip([3.0f32, 5.5, 8.0].iter()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_fft_convolve_is_correlation_with_reversed_kernel() {
+        let signal: Vec<f64> = (0..50).map(|i| ((i as f64) * 0.3).sin()).collect();
+        let kernel: Vec<f64> = vec![0.2, -0.7, 1.1, 0.4];
+        let reversed: Vec<f64> = kernel.iter().rev().cloned().collect();
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let conv = fft_convolve_1d(&signal, &kernel, mode).unwrap();
+            let corr = crate::fft_correlate_1d(&signal, &reversed, mode).unwrap();
+            assert_eq!(conv.len(), corr.len());
+            for (a, b) in conv.iter().zip(corr.iter()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_convolve_empty_inputs() {
+        assert!(fft_convolve_1d::<f32>(&[], &[1.0], Mode::Full).unwrap().is_empty());
+        assert!(fft_convolve_1d::<f32>(&[1.0], &[], Mode::Valid).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! 2D cross-correlation for images and spectrograms
+//!
+//! Inputs are row-major slices with an explicit `(rows, cols)` shape, so a spectrogram with
+//! `frames` rows of `bins` columns is passed as `(frames, bins)`. Each axis follows the 1D [`Mode`]
+//! conventions independently: Full output index `(i, j)` is where `template[rows-1][cols-1]` aligns
+//! with `signal[i][j]`, Same output is centered with [`Mode::full_offset`] on both axes, and Valid
+//! keeps only positions where the template lies entirely inside the signal. This matches
+//! `scipy.signal.correlate` on 2D arrays; Full and Valid output also match `scipy.signal.correlate2d`.
+//!
+//! The transform is a real 2D FFT: realfft along each row, then a complex FFT down each column of
+//! the half spectrum, with plans taken from the same thread-local caches as the 1D functions.
+
+use realfft::num_complex::Complex;
+use realfft::RealToComplex;
+use rustfft::Fft;
+
+use crate::{plan_complex_fft, plan_fft, FftCorrelationError, FftFloat, FftSizing, Mode, Result};
+
+/// Correlate two row-major 2D arrays using FFT
+///
+/// `signal_shape` and `template_shape` are `(rows, cols)`. Returns the output values in row-major
+/// order together with the output shape, which is `mode.output_len` applied to each axis:
+///
+/// - `Mode::Full`: `(R + r - 1, C + c - 1)`
+/// - `Mode::Same`: `(R, C)`
+/// - `Mode::Valid`: `(R - r + 1, C - c + 1)`, or empty if the template is larger on either axis
+///
+/// Returns an empty vector if either input has a zero dimension.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::ShapeMismatch` if a slice length differs from `rows * cols` of its
+/// shape, and `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # References
+///
+/// - scipy.signal.correlate2d: https://docs.scipy.org/doc/scipy/reference/generated/scipy.signal.correlate2d.html
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_2d, Mode};
+///
+/// // 4x5 image containing the 2x2 patch at row 1, column 2
+/// let mut image = vec![0.0f64; 20];
+/// let patch = [1.0, 2.0, 3.0, 4.0];
+/// image[7] = 1.0;
+/// image[8] = 2.0;
+/// image[12] = 3.0;
+/// image[13] = 4.0;
+///
+/// let (result, shape) = fft_correlate_2d(&image, (4, 5), &patch, (2, 2), Mode::Valid).unwrap();
+/// assert_eq!(shape, (3, 4));
+/// let best = result.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap().0;
+/// assert_eq!((best / shape.1, best % shape.1), (1, 2));
+/// ```
+pub fn fft_correlate_2d<T: FftFloat>(
+    signal: &[T],
+    signal_shape: (usize, usize),
+    template: &[T],
+    template_shape: (usize, usize),
+    mode: Mode,
+) -> Result<(Vec<T>, (usize, usize))> {
+    check_shape(signal, signal_shape)?;
+    check_shape(template, template_shape)?;
+
+    let (rows, cols) = signal_shape;
+    let (template_rows, template_cols) = template_shape;
+    let out_shape = (mode.output_len(rows, template_rows), mode.output_len(cols, template_cols));
+    if out_shape.0 == 0 || out_shape.1 == 0 {
+        return Ok((Vec::new(), out_shape));
+    }
+
+    let fft_rows = FftSizing::default().fft_len(rows + template_rows - 1);
+    let fft_cols = FftSizing::default().fft_len(cols + template_cols - 1);
+    let (r2c, c2r) = plan_fft(fft_cols);
+    let (column_forward, column_inverse) = plan_complex_fft(fft_rows);
+    let planes = Planes { fft_rows, fft_cols, r2c: r2c.as_ref(), column_forward: column_forward.as_ref() };
+
+    let mut product = planes.spectrum(signal, signal_shape, false)?;
+    let template_spectrum = planes.spectrum(template, template_shape, true)?;
+    for (s, t) in product.iter_mut().zip(template_spectrum.iter()) {
+        *s *= t;
+    }
+    column_inverse.process(&mut product);
+
+    // Inverse row transforms, only for the rows that survive trimming
+    let row_start = mode.full_offset(template_rows);
+    let col_start = mode.full_offset(template_cols);
+    let normalization = T::from_len(fft_rows) * T::from_len(fft_cols);
+    let mut row_spectrum = c2r.make_input_vec();
+    let mut row_time = c2r.make_output_vec();
+    let mut output = Vec::with_capacity(out_shape.0 * out_shape.1);
+    for r in row_start..row_start + out_shape.0 {
+        for (k, value) in row_spectrum.iter_mut().enumerate() {
+            *value = product[k * fft_rows + r];
+        }
+        zero_real_bins(&mut row_spectrum, fft_cols);
+        c2r.process(&mut row_spectrum, &mut row_time)
+            .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+        output.extend(row_time[col_start..col_start + out_shape.1].iter().map(|&x| x / normalization));
+    }
+    Ok((output, out_shape))
+}
+
+/// Ensure a row-major slice holds exactly `rows * cols` samples
+pub(crate) fn check_shape<T>(data: &[T], shape: (usize, usize)) -> Result<()> {
+    let expected = shape.0 * shape.1;
+    if data.len() != expected {
+        return Err(FftCorrelationError::ShapeMismatch { expected, actual: data.len() });
+    }
+    Ok(())
+}
+
+// Clear round-off in the imaginary parts that a real signal's spectrum has at DC and Nyquist;
+// realfft rejects inverse input where they are non-zero
+pub(crate) fn zero_real_bins<T: FftFloat>(spectrum: &mut [Complex<T>], fft_len: usize) {
+    spectrum[0].im = T::zero();
+    if fft_len.is_multiple_of(2) {
+        if let Some(last) = spectrum.last_mut() {
+            last.im = T::zero();
+        }
+    }
+}
+
+// Plans and padded dimensions shared by the signal and template transforms
+struct Planes<'a, T: FftFloat> {
+    fft_rows: usize,
+    fft_cols: usize,
+    r2c: &'a dyn RealToComplex<T>,
+    column_forward: &'a dyn Fft<T>,
+}
+
+impl<T: FftFloat> Planes<'_, T> {
+    // Zero-padded 2D spectrum stored column-major (`fft_cols / 2 + 1` columns of `fft_rows`), so
+    // the column FFTs run over contiguous chunks. `reversed` flips the input on both axes.
+    fn spectrum(&self, data: &[T], shape: (usize, usize), reversed: bool) -> Result<Vec<Complex<T>>> {
+        let (rows, cols) = shape;
+        let mut row_time = vec![T::zero(); self.fft_cols];
+        let mut row_spectrum = self.r2c.make_output_vec();
+        let mut columns = vec![Complex::new(T::zero(), T::zero()); row_spectrum.len() * self.fft_rows];
+
+        for r in 0..rows {
+            row_time.fill(T::zero());
+            if reversed {
+                let source = &data[(rows - 1 - r) * cols..(rows - r) * cols];
+                for (dst, &val) in row_time.iter_mut().zip(source.iter().rev()) {
+                    *dst = val;
+                }
+            } else {
+                row_time[..cols].copy_from_slice(&data[r * cols..(r + 1) * cols]);
+            }
+            self.r2c.process(&mut row_time, &mut row_spectrum)
+                .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed: {:?}", e)))?;
+            for (k, &value) in row_spectrum.iter().enumerate() {
+                columns[k * self.fft_rows + r] = value;
+            }
+        }
+
+        self.column_forward.process(&mut columns);
+        Ok(columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    // Naive 2D correlation at every Full-mode position
+    fn naive_full_2d(signal: &[f64], shape: (usize, usize), template: &[f64], template_shape: (usize, usize)) -> Vec<f64> {
+        let (rows, cols) = shape;
+        let (t_rows, t_cols) = template_shape;
+        let full_cols = cols + t_cols - 1;
+        let mut out = vec![0.0; (rows + t_rows - 1) * full_cols];
+        for i in 0..rows + t_rows - 1 {
+            for j in 0..full_cols {
+                let mut sum = 0.0;
+                for p in 0..t_rows {
+                    for q in 0..t_cols {
+                        let r = i as isize + p as isize - (t_rows as isize - 1);
+                        let c = j as isize + q as isize - (t_cols as isize - 1);
+                        if r >= 0 && c >= 0 && (r as usize) < rows && (c as usize) < cols {
+                            sum += signal[r as usize * cols + c as usize] * template[p * t_cols + q];
+                        }
+                    }
+                }
+                out[i * full_cols + j] = sum;
+            }
+        }
+        out
+    }
+
+    fn test_array(shape: (usize, usize), seed: f64) -> Vec<f64> {
+        (0..shape.0 * shape.1).map(|i| ((i as f64) * seed).sin() + 0.1 * (i % 3) as f64).collect()
+    }
+
+    #[test]
+    fn test_fft_correlate_2d_matches_naive_across_modes() {
+        let shapes = [(1, 1), (1, 5), (4, 1), (3, 4), (5, 7), (6, 6)];
+        for &shape in &shapes {
+            for &template_shape in &shapes {
+                let signal = test_array(shape, 0.7);
+                let template = test_array(template_shape, 1.3);
+                let full = naive_full_2d(&signal, shape, &template, template_shape);
+                let full_cols = shape.1 + template_shape.1 - 1;
+
+                for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                    let (result, out_shape) = fft_correlate_2d(&signal, shape, &template, template_shape, mode).unwrap();
+                    let expected_shape =
+                        (mode.output_len(shape.0, template_shape.0), mode.output_len(shape.1, template_shape.1));
+                    assert_eq!(out_shape, expected_shape, "{:?} * {:?} {:?}", shape, template_shape, mode);
+                    if out_shape.0 == 0 || out_shape.1 == 0 {
+                        assert!(result.is_empty());
+                        continue;
+                    }
+                    let (r0, c0) = (mode.full_offset(template_shape.0), mode.full_offset(template_shape.1));
+                    for i in 0..out_shape.0 {
+                        for j in 0..out_shape.1 {
+                            let a = result[i * out_shape.1 + j];
+                            let b = full[(i + r0) * full_cols + j + c0];
+                            assert!((a - b).abs() < 1e-10, "{:?} * {:?} {:?} at ({}, {})", shape, template_shape, mode, i, j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlate_2d_single_row_matches_1d() {
+        let signal = test_array((1, 40), 0.3);
+        let template = test_array((1, 7), 0.9);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let (result, shape) = fft_correlate_2d(&signal, (1, 40), &template, (1, 7), mode).unwrap();
+            let expected = fft_correlate_1d(&signal, &template, mode).unwrap();
+            assert_eq!(shape, (1, expected.len()));
+            for (a, b) in result.iter().zip(expected.iter()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlate_2d_finds_patch_in_spectrogram_f32() {
+        let (rows, cols) = (32, 48);
+        let mut image: Vec<f32> = (0..rows * cols).map(|i| ((i as f32) * 0.37).sin() * 0.1).collect();
+        let patch: Vec<f32> = vec![1.0, -1.0, 2.0, 0.5, 3.0, -2.0, 1.5, 1.0, -0.5, 2.5, 1.0, -1.5];
+        for p in 0..3 {
+            for q in 0..4 {
+                image[(20 + p) * cols + 9 + q] += patch[p * 4 + q];
+            }
+        }
+        let (result, shape) = fft_correlate_2d(&image, (rows, cols), &patch, (3, 4), Mode::Valid).unwrap();
+        let best = result.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap().0;
+        assert_eq!((best / shape.1, best % shape.1), (20, 9));
+    }
+
+    #[test]
+    fn test_fft_correlate_2d_shape_errors_and_empty() {
+        let err = fft_correlate_2d(&[1.0f32; 5], (2, 3), &[1.0], (1, 1), Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::ShapeMismatch { expected: 6, actual: 5 }));
+        assert!(fft_correlate_2d(&[1.0f32; 4], (2, 2), &[1.0; 2], (1, 1), Mode::Full).is_err());
+
+        let (result, shape) = fft_correlate_2d::<f64>(&[], (0, 4), &[1.0], (1, 1), Mode::Same).unwrap();
+        assert!(result.is_empty());
+        assert_eq!(shape, (0, 4));
+        let (result, shape) = fft_correlate_2d(&[1.0f64; 6], (2, 3), &[1.0; 3], (3, 1), Mode::Valid).unwrap();
+        assert!(result.is_empty());
+        assert_eq!(shape, (0, 3));
+    }
+}
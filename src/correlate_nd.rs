@@ -0,0 +1,491 @@
+//! N-dimensional correlation along chosen axes
+//!
+//! [`fft_correlate_nd`] correlates row-major N-D arrays along a subset of their axes, like
+//! `scipy.signal.fftconvolve(..., axes=...)` for correlation. Every correlated axis follows the 1D
+//! [`Mode`] conventions on its own: output length [`Mode::output_len`], Full index `k` where the last
+//! template sample on that axis aligns with signal index `k`, and Same/Valid windows starting at
+//! [`Mode::full_offset`]. The remaining axes are not correlated but broadcast: they must have equal
+//! sizes, or size 1 in one of the inputs, exactly as in numpy broadcasting. This lets a stack of
+//! spectrograms (`(channels, frames, bins)`) be matched against one 2D template of shape
+//! `(1, rows, cols)` along axes `[1, 2]`.
+//!
+//! The transform is a real FFT along the last correlated axis followed by complex FFTs along the
+//! other correlated axes. With the `ndarray` feature, [`fft_correlate_array`] accepts array views.
+
+use realfft::num_complex::Complex;
+use rustfft::Fft;
+
+use crate::correlate2d::zero_real_bins;
+use crate::{plan_complex_fft, plan_fft, FftCorrelationError, FftFloat, FftSizing, Mode, Result};
+
+/// Correlate two row-major N-D arrays using FFT along `axes`
+///
+/// `signal_shape` and `template_shape` must have the same rank. The output shape is
+/// `mode.output_len(signal_shape[d], template_shape[d])` on every axis `d` in `axes`, and the
+/// broadcast size on every other axis. Returns the output values in row-major order together with
+/// the output shape.
+///
+/// With no axes the result is the broadcast elementwise product. Returns an empty vector if the
+/// output shape has a zero dimension.
+///
+/// # Errors
+///
+/// - `FftCorrelationError::ShapeMismatch` if a slice length differs from the product of its shape
+/// - `FftCorrelationError::InvalidAxis` if an axis is out of range or listed twice
+/// - `FftCorrelationError::IncompatibleShapes` if the ranks differ or a non-correlated axis cannot
+///   be broadcast
+/// - `FftCorrelationError::FftProcessing` if FFT processing fails
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_nd, Mode};
+///
+/// // Two channels of 8 samples, each correlated with the same 3-tap template
+/// let signal: Vec<f64> = (0..16).map(|i| (i % 8) as f64).collect();
+/// let template = [1.0, 0.0, -1.0];
+/// let (result, shape) = fft_correlate_nd(&signal, &[2, 8], &template, &[1, 3], &[1], Mode::Valid).unwrap();
+/// assert_eq!(shape, vec![2, 6]);
+/// for value in result {
+///     assert!((value + 2.0).abs() < 1e-12);
+/// }
+/// ```
+pub fn fft_correlate_nd<T: FftFloat>(
+    signal: &[T],
+    signal_shape: &[usize],
+    template: &[T],
+    template_shape: &[usize],
+    axes: &[usize],
+    mode: Mode,
+) -> Result<(Vec<T>, Vec<usize>)> {
+    check_len(signal, signal_shape)?;
+    check_len(template, template_shape)?;
+    let incompatible = || FftCorrelationError::IncompatibleShapes {
+        signal: signal_shape.to_vec(),
+        template: template_shape.to_vec(),
+    };
+    let ndim = signal_shape.len();
+    if template_shape.len() != ndim {
+        return Err(incompatible());
+    }
+
+    let mut correlated = vec![false; ndim];
+    for &axis in axes {
+        if axis >= ndim || correlated[axis] {
+            return Err(FftCorrelationError::InvalidAxis { axis, ndim });
+        }
+        correlated[axis] = true;
+    }
+
+    let mut out_shape = Vec::with_capacity(ndim);
+    for d in 0..ndim {
+        let (s, t) = (signal_shape[d], template_shape[d]);
+        out_shape.push(if correlated[d] {
+            mode.output_len(s, t)
+        } else if s == t || t == 1 {
+            s
+        } else if s == 1 {
+            t
+        } else {
+            return Err(incompatible());
+        });
+    }
+    if out_shape.contains(&0) {
+        return Ok((Vec::new(), out_shape));
+    }
+
+    // The last correlated axis gets the real transform; without one there is nothing to correlate
+    let Some(real_axis) = (0..ndim).rev().find(|&d| correlated[d]) else {
+        let product = broadcast_zip(signal, signal_shape, template, template_shape, &out_shape, |s, t| s * t);
+        return Ok((product, out_shape));
+    };
+
+    let fft_lens: Vec<usize> = (0..ndim)
+        .map(|d| if correlated[d] { FftSizing::default().fft_len(signal_shape[d] + template_shape[d] - 1) } else { 0 })
+        .collect();
+    let plan = NdPlan { correlated: &correlated, fft_lens: &fft_lens, real_axis };
+
+    let (signal_spectrum, signal_spectrum_shape) = plan.spectrum(signal, signal_shape, false)?;
+    let (template_spectrum, template_spectrum_shape) = plan.spectrum(template, template_shape, true)?;
+    let product_shape: Vec<usize> = signal_spectrum_shape
+        .iter()
+        .zip(template_spectrum_shape.iter())
+        .map(|(&s, &t)| s.max(t))
+        .collect();
+    let mut product = broadcast_zip(
+        &signal_spectrum,
+        &signal_spectrum_shape,
+        &template_spectrum,
+        &template_spectrum_shape,
+        &product_shape,
+        |s, t| s * t,
+    );
+
+    for d in plan.complex_axes() {
+        let (_, inverse) = plan_complex_fft(fft_lens[d]);
+        transform_lanes(&mut product, &product_shape, d, inverse.as_ref());
+    }
+
+    // Inverse real transform along the real axis
+    let fft_len = fft_lens[real_axis];
+    let (_, c2r) = plan_fft::<T>(fft_len);
+    let mut full_shape = product_shape.clone();
+    full_shape[real_axis] = fft_len;
+    let mut full = vec![T::zero(); numel(&full_shape)];
+    let mut lane_spectrum = c2r.make_input_vec();
+    let mut lane_time = c2r.make_output_vec();
+    let (in_stride, out_stride) = (strides(&product_shape)[real_axis], strides(&full_shape)[real_axis]);
+    for (src, dst) in lane_starts(&product_shape, real_axis).zip(lane_starts(&full_shape, real_axis)) {
+        for (k, value) in lane_spectrum.iter_mut().enumerate() {
+            *value = product[src + k * in_stride];
+        }
+        zero_real_bins(&mut lane_spectrum, fft_len);
+        c2r.process(&mut lane_spectrum, &mut lane_time)
+            .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+        for (k, &value) in lane_time.iter().enumerate() {
+            full[dst + k * out_stride] = value;
+        }
+    }
+
+    // Normalize and trim every correlated axis to `mode`
+    let normalization = (0..ndim).filter(|&d| correlated[d]).fold(T::one(), |acc, d| acc * T::from_len(fft_lens[d]));
+    let offsets: Vec<usize> =
+        (0..ndim).map(|d| if correlated[d] { mode.full_offset(template_shape[d]) } else { 0 }).collect();
+    let full_strides = strides(&full_shape);
+    let mut output = Vec::with_capacity(numel(&out_shape));
+    for_each_index(&out_shape, |index| {
+        let src: usize = (0..ndim).map(|d| (index[d] + offsets[d]) * full_strides[d]).sum();
+        output.push(full[src] / normalization);
+    });
+    Ok((output, out_shape))
+}
+
+/// Correlate two `ndarray` arrays using FFT along `axes`
+///
+/// Array view wrapper around [`fft_correlate_nd`] with the same shape rules and errors. Views in
+/// any memory layout are accepted; the result is in standard (row-major) layout.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_array, Mode};
+/// use ndarray::{array, Array3};
+///
+/// let volume = Array3::<f32>::ones((4, 6, 5));
+/// let kernel = array![[[1.0f32, 1.0], [1.0, 1.0]]];
+/// let result = fft_correlate_array(volume.view(), kernel.view(), &[0, 1, 2], Mode::Valid).unwrap();
+/// assert_eq!(result.shape(), &[4, 5, 4]);
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn fft_correlate_array<T: FftFloat, D: ndarray::Dimension>(
+    signal: ndarray::ArrayView<'_, T, D>,
+    template: ndarray::ArrayView<'_, T, D>,
+    axes: &[usize],
+    mode: Mode,
+) -> Result<ndarray::Array<T, D>> {
+    let signal_standard = signal.as_standard_layout();
+    let template_standard = template.as_standard_layout();
+    let (values, shape) = fft_correlate_nd(
+        signal_standard.as_slice().expect("standard layout is contiguous"),
+        signal.shape(),
+        template_standard.as_slice().expect("standard layout is contiguous"),
+        template.shape(),
+        axes,
+        mode,
+    )?;
+
+    let mut dim = signal.raw_dim();
+    for (d, &len) in shape.iter().enumerate() {
+        dim[d] = len;
+    }
+    Ok(ndarray::Array::from_shape_vec(dim, values).expect("output length matches its shape"))
+}
+
+// Padded FFT lengths and axis roles shared by the signal and template transforms
+struct NdPlan<'a> {
+    correlated: &'a [bool],
+    fft_lens: &'a [usize],
+    real_axis: usize,
+}
+
+impl NdPlan<'_> {
+    // Correlated axes transformed with complex FFTs
+    fn complex_axes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.correlated.len()).filter(|&d| self.correlated[d] && d != self.real_axis)
+    }
+
+    // Zero-padded spectrum of `data` and its shape; `reversed` flips the correlated axes
+    fn spectrum<T: FftFloat>(&self, data: &[T], shape: &[usize], reversed: bool) -> Result<(Vec<Complex<T>>, Vec<usize>)> {
+        let ndim = shape.len();
+        let padded_shape: Vec<usize> =
+            (0..ndim).map(|d| if self.correlated[d] { self.fft_lens[d] } else { shape[d] }).collect();
+        let padded_strides = strides(&padded_shape);
+        let mut padded = vec![T::zero(); numel(&padded_shape)];
+        let mut flat = 0;
+        for_each_index(shape, |index| {
+            let dst: usize = (0..ndim)
+                .map(|d| {
+                    let i = if reversed && self.correlated[d] { shape[d] - 1 - index[d] } else { index[d] };
+                    i * padded_strides[d]
+                })
+                .sum();
+            padded[dst] = data[flat];
+            flat += 1;
+        });
+
+        // Real transform along the real axis
+        let fft_len = self.fft_lens[self.real_axis];
+        let (r2c, _) = plan_fft::<T>(fft_len);
+        let mut spectrum_shape = padded_shape.clone();
+        spectrum_shape[self.real_axis] = fft_len / 2 + 1;
+        let mut spectrum = vec![Complex::new(T::zero(), T::zero()); numel(&spectrum_shape)];
+        let mut lane_time = r2c.make_input_vec();
+        let mut lane_spectrum = r2c.make_output_vec();
+        let (in_stride, out_stride) = (padded_strides[self.real_axis], strides(&spectrum_shape)[self.real_axis]);
+        for (src, dst) in lane_starts(&padded_shape, self.real_axis).zip(lane_starts(&spectrum_shape, self.real_axis)) {
+            for (k, value) in lane_time.iter_mut().enumerate() {
+                *value = padded[src + k * in_stride];
+            }
+            r2c.process(&mut lane_time, &mut lane_spectrum)
+                .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed: {:?}", e)))?;
+            for (k, &value) in lane_spectrum.iter().enumerate() {
+                spectrum[dst + k * out_stride] = value;
+            }
+        }
+
+        for d in self.complex_axes() {
+            let (forward, _) = plan_complex_fft(self.fft_lens[d]);
+            transform_lanes(&mut spectrum, &spectrum_shape, d, forward.as_ref());
+        }
+        Ok((spectrum, spectrum_shape))
+    }
+}
+
+// Ensure a row-major slice holds exactly the product of `shape` samples
+fn check_len<T>(data: &[T], shape: &[usize]) -> Result<()> {
+    let expected = numel(shape);
+    if data.len() != expected {
+        return Err(FftCorrelationError::ShapeMismatch { expected, actual: data.len() });
+    }
+    Ok(())
+}
+
+fn numel(shape: &[usize]) -> usize {
+    shape.iter().product()
+}
+
+// Row-major element strides
+fn strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for d in (0..shape.len().saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1];
+    }
+    strides
+}
+
+// Flat offsets of the first element of every 1D lane along `axis`, in row-major lane order
+fn lane_starts(shape: &[usize], axis: usize) -> impl Iterator<Item = usize> {
+    let inner: usize = shape[axis + 1..].iter().product();
+    let outer: usize = shape[..axis].iter().product();
+    let block = shape[axis] * inner;
+    (0..outer).flat_map(move |o| (0..inner).map(move |i| o * block + i))
+}
+
+// Call `f` with every multi-index of `shape` in row-major order
+fn for_each_index(shape: &[usize], mut f: impl FnMut(&[usize])) {
+    let mut index = vec![0; shape.len()];
+    for _ in 0..numel(shape) {
+        f(&index);
+        for d in (0..shape.len()).rev() {
+            index[d] += 1;
+            if index[d] < shape[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+}
+
+// Elementwise `f(a, b)` over `out_shape`, repeating size-1 axes of either input
+fn broadcast_zip<A: Copy, B: Copy, R>(
+    a: &[A],
+    a_shape: &[usize],
+    b: &[B],
+    b_shape: &[usize],
+    out_shape: &[usize],
+    f: impl Fn(A, B) -> R,
+) -> Vec<R> {
+    let broadcast_strides = |shape: &[usize]| -> Vec<usize> {
+        strides(shape).into_iter().zip(shape).map(|(stride, &len)| if len == 1 { 0 } else { stride }).collect()
+    };
+    let (a_strides, b_strides) = (broadcast_strides(a_shape), broadcast_strides(b_shape));
+    let mut out = Vec::with_capacity(numel(out_shape));
+    for_each_index(out_shape, |index| {
+        let ia: usize = index.iter().zip(&a_strides).map(|(i, s)| i * s).sum();
+        let ib: usize = index.iter().zip(&b_strides).map(|(i, s)| i * s).sum();
+        out.push(f(a[ia], b[ib]));
+    });
+    out
+}
+
+// In-place complex FFT of every lane along `axis`
+fn transform_lanes<T: FftFloat>(data: &mut [Complex<T>], shape: &[usize], axis: usize, fft: &dyn Fft<T>) {
+    let stride = strides(shape)[axis];
+    let mut lane = vec![Complex::new(T::zero(), T::zero()); shape[axis]];
+    let mut scratch = vec![Complex::new(T::zero(), T::zero()); fft.get_inplace_scratch_len()];
+    for start in lane_starts(shape, axis) {
+        for (k, value) in lane.iter_mut().enumerate() {
+            *value = data[start + k * stride];
+        }
+        fft.process_with_scratch(&mut lane, &mut scratch);
+        for (k, &value) in lane.iter().enumerate() {
+            data[start + k * stride] = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_2d;
+
+    fn test_array(shape: &[usize], seed: f64) -> Vec<f64> {
+        (0..numel(shape)).map(|i| ((i as f64) * seed).sin() + 0.1 * (i % 4) as f64).collect()
+    }
+
+    // Naive correlation along `axes` with broadcasting on the other axes
+    fn naive_nd(
+        signal: &[f64],
+        signal_shape: &[usize],
+        template: &[f64],
+        template_shape: &[usize],
+        axes: &[usize],
+        mode: Mode,
+        out_shape: &[usize],
+    ) -> Vec<f64> {
+        let ndim = signal_shape.len();
+        let (s_strides, t_strides) = (strides(signal_shape), strides(template_shape));
+        let mut out = Vec::new();
+        for_each_index(out_shape, |o| {
+            let mut sum = 0.0;
+            for_each_index(template_shape, |p| {
+                let mut s_flat = 0isize;
+                let mut t_flat = 0usize;
+                for d in 0..ndim {
+                    let (s, t) = if axes.contains(&d) {
+                        let lag = mode.lag_at(o[d], template_shape[d]);
+                        (lag + p[d] as isize, p[d])
+                    } else {
+                        // Template index must follow the output index (or the size-1 broadcast)
+                        let t = if template_shape[d] == 1 { 0 } else { o[d] };
+                        if p[d] != t {
+                            return;
+                        }
+                        ((if signal_shape[d] == 1 { 0 } else { o[d] }) as isize, t)
+                    };
+                    if s < 0 || s as usize >= signal_shape[d] {
+                        return;
+                    }
+                    s_flat += s * s_strides[d] as isize;
+                    t_flat += t * t_strides[d];
+                }
+                sum += signal[s_flat as usize] * template[t_flat];
+            });
+            out.push(sum);
+        });
+        out
+    }
+
+    #[test]
+    fn test_fft_correlate_nd_matches_naive() {
+        let cases: [(&[usize], &[usize], &[usize]); 7] = [
+            (&[4, 5, 3], &[2, 3, 3], &[0, 1]),
+            (&[4, 5, 3], &[2, 3, 3], &[0, 1, 2]),
+            (&[4, 5, 3], &[4, 5, 2], &[2]),
+            (&[4, 5, 3], &[3, 5, 2], &[0, 2]),
+            (&[4, 5, 3], &[1, 3, 1], &[1]),
+            (&[1, 6, 2], &[3, 2, 2], &[1]),
+            (&[3, 2, 4, 5], &[2, 1, 3, 5], &[0, 2]),
+        ];
+        for (signal_shape, template_shape, axes) in cases {
+            let signal = test_array(signal_shape, 0.7);
+            let template = test_array(template_shape, 1.9);
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                let (result, shape) = fft_correlate_nd(&signal, signal_shape, &template, template_shape, axes, mode).unwrap();
+                let expected = naive_nd(&signal, signal_shape, &template, template_shape, axes, mode, &shape);
+                assert_eq!(result.len(), numel(&shape));
+                for (a, b) in result.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-10, "{:?} * {:?} axes {:?} {:?}", signal_shape, template_shape, axes, mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlate_nd_matches_2d_and_axis_order() {
+        let signal = test_array(&[6, 7], 0.4);
+        let template = test_array(&[3, 2], 1.1);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let (expected, expected_shape) = fft_correlate_2d(&signal, (6, 7), &template, (3, 2), mode).unwrap();
+            for axes in [[0, 1], [1, 0]] {
+                let (result, shape) = fft_correlate_nd(&signal, &[6, 7], &template, &[3, 2], &axes, mode).unwrap();
+                assert_eq!(shape, vec![expected_shape.0, expected_shape.1]);
+                for (a, b) in result.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlate_nd_without_axes_is_broadcast_product() {
+        let (result, shape) = fft_correlate_nd(&[1.0f32, 2.0, 3.0, 4.0], &[2, 2], &[10.0, 100.0], &[1, 2], &[], Mode::Full).unwrap();
+        assert_eq!(shape, vec![2, 2]);
+        assert_eq!(result, vec![10.0, 200.0, 30.0, 400.0]);
+    }
+
+    #[test]
+    fn test_fft_correlate_nd_errors_and_empty() {
+        let signal = [0.0f64; 12];
+        let err = fft_correlate_nd(&signal, &[3, 4], &[1.0; 2], &[1, 2], &[2], Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::InvalidAxis { axis: 2, ndim: 2 }));
+        let err = fft_correlate_nd(&signal, &[3, 4], &[1.0; 2], &[1, 2], &[1, 1], Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::InvalidAxis { axis: 1, ndim: 2 }));
+        let err = fft_correlate_nd(&signal, &[3, 4], &[1.0; 4], &[2, 2], &[1], Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::IncompatibleShapes { .. }));
+        let err = fft_correlate_nd(&signal, &[3, 4], &[1.0; 2], &[2], &[0], Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::IncompatibleShapes { .. }));
+        let err = fft_correlate_nd(&signal, &[3, 5], &[1.0], &[1, 1], &[0], Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::ShapeMismatch { expected: 15, actual: 12 }));
+
+        let (result, shape) = fft_correlate_nd(&signal, &[3, 4], &[1.0; 5], &[1, 5], &[1], Mode::Valid).unwrap();
+        assert!(result.is_empty());
+        assert_eq!(shape, vec![3, 0]);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_fft_correlate_array_matches_slices_for_non_standard_layout() {
+        let data = test_array(&[5, 6], 0.3);
+        let signal = ndarray::Array2::from_shape_vec((5, 6), data).unwrap();
+        let template = ndarray::array![[1.0, -0.5], [0.25, 2.0], [0.5, 0.0]];
+        // Transposed views exercise the standard-layout conversion
+        let result = fft_correlate_array(signal.t(), template.t(), &[0, 1], Mode::Same).unwrap();
+
+        let signal_t = signal.t().as_standard_layout().to_owned();
+        let template_t = template.t().as_standard_layout().to_owned();
+        let (expected, shape) = fft_correlate_nd(
+            signal_t.as_slice().unwrap(),
+            &[6, 5],
+            template_t.as_slice().unwrap(),
+            &[2, 3],
+            &[0, 1],
+            Mode::Same,
+        )
+        .unwrap();
+        assert_eq!(result.shape(), shape.as_slice());
+        for (a, b) in result.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Correlation output that carries its lag axis
+//!
+//! A bare `Vec<T>` leaves every caller to redo the mode-dependent index-to-lag mapping described
+//! on [`Mode`]. [`Correlation`] keeps the values together with the mode and input lengths and
+//! answers the lag questions directly.
+
+use std::ops::RangeInclusive;
+
+use crate::{Correlator, FftFloat, Mode, Result};
+
+/// Correlation values with their mode, input lengths and lag axis
+///
+/// Lag `l` means `template[0]` aligns with `signal[l]` (see [`Mode::lag_at`]). Outputs of a
+/// [`Correlator`] configured with a lag range have no [`Mode`]; they report that range through
+/// [`lag_range`](Self::lag_range) instead.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d_with_lags, Mode};
+///
+/// let signal = [0.0f64, 0.0, 0.0, 1.0, 2.0, 1.0, 0.0];
+/// let template = [1.0, 2.0, 1.0];
+/// let result = fft_correlate_1d_with_lags(&signal, &template, Mode::Same).unwrap();
+/// assert_eq!(result.argmax_lag(), Some(3));
+/// assert_eq!(result.lags(), vec![-1, 0, 1, 2, 3, 4, 5]);
+/// assert!((result.value_at_lag(3).unwrap() - 6.0).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correlation<T> {
+    values: Vec<T>,
+    axis: LagAxis,
+    signal_len: usize,
+    template_len: usize,
+    first_lag: isize,
+}
+
+impl<T: FftFloat> Correlation<T> {
+    /// Wrap `values` computed in `mode` from inputs of the given lengths
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values.len()` is not `mode.output_len(signal_len, template_len)`.
+    pub fn new(values: Vec<T>, mode: Mode, signal_len: usize, template_len: usize) -> Self {
+        assert_eq!(
+            values.len(),
+            mode.output_len(signal_len, template_len),
+            "{:?} output length does not match input lengths {} and {}",
+            mode,
+            signal_len,
+            template_len
+        );
+        let first_lag = mode.lag_at(0, template_len);
+        Self { values, axis: LagAxis::Mode(mode), signal_len, template_len, first_lag }
+    }
+
+    // Values for every lag of `lags`, as produced by a correlator with that lag range; empty if
+    // either input was empty
+    pub(crate) fn from_lag_range(
+        values: Vec<T>,
+        lags: RangeInclusive<isize>,
+        signal_len: usize,
+        template_len: usize,
+    ) -> Self {
+        debug_assert!(values.is_empty() || lags.clone().count() == values.len());
+        let first_lag = *lags.start();
+        Self { values, axis: LagAxis::Range(lags), signal_len, template_len, first_lag }
+    }
+
+    /// Correlation values, one per lag
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Take the correlation values
+    pub fn into_values(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Mode the values were computed in, or `None` if they cover a lag range
+    pub fn mode(&self) -> Option<Mode> {
+        match self.axis {
+            LagAxis::Mode(mode) => Some(mode),
+            LagAxis::Range(_) => None,
+        }
+    }
+
+    /// Lag range the values were computed for, or `None` if they follow a [`Mode`]
+    pub fn lag_range(&self) -> Option<RangeInclusive<isize>> {
+        match &self.axis {
+            LagAxis::Mode(_) => None,
+            LagAxis::Range(lags) => Some(lags.clone()),
+        }
+    }
+
+    /// Length of the signal input
+    pub fn signal_len(&self) -> usize {
+        self.signal_len
+    }
+
+    /// Length of the template input
+    pub fn template_len(&self) -> usize {
+        self.template_len
+    }
+
+    /// Number of values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether there are no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Signed lag of value `index`
+    pub fn lag_at(&self, index: usize) -> isize {
+        self.first_lag + index as isize
+    }
+
+    /// Index of the value at `lag`, or `None` if that lag is not in the output
+    pub fn index_of_lag(&self, lag: isize) -> Option<usize> {
+        lag.checked_sub(self.first_lag)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .filter(|&index| index < self.values.len())
+    }
+
+    /// Lag of every value
+    pub fn lags(&self) -> Vec<isize> {
+        (0..self.values.len()).map(|index| self.lag_at(index)).collect()
+    }
+
+    /// Value at `lag`, or `None` if that lag is not in the output
+    pub fn value_at_lag(&self, lag: isize) -> Option<T> {
+        self.index_of_lag(lag).map(|index| self.values[index])
+    }
+
+    /// Index of the largest finite value; the first one wins ties
+    ///
+    /// Returns `None` if there are no finite values.
+    pub fn argmax(&self) -> Option<usize> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.is_finite())
+            .fold(None, |best: Option<(usize, T)>, (index, &v)| match best {
+                Some((_, best_value)) if best_value >= v => best,
+                _ => Some((index, v)),
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Lag of the largest finite value (see [`argmax`](Self::argmax))
+    pub fn argmax_lag(&self) -> Option<isize> {
+        self.argmax().map(|index| self.lag_at(index))
+    }
+}
+
+// What the values of a `Correlation` span
+#[derive(Debug, Clone, PartialEq)]
+enum LagAxis {
+    Mode(Mode),
+    Range(RangeInclusive<isize>),
+}
+
+impl<T> AsRef<[T]> for Correlation<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.values
+    }
+}
+
+/// Correlate two 1D signals using FFT and keep the lag metadata
+///
+/// Same values as [`fft_correlate_1d`](crate::fft_correlate_1d), wrapped in a [`Correlation`].
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+pub fn fft_correlate_1d_with_lags<T: FftFloat>(signal: &[T], template: &[T], mode: Mode) -> Result<Correlation<T>> {
+    Correlator::builder().mode(mode).build()?.correlate_with_lags(signal, template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    #[test]
+    fn test_lag_axis_matches_mode_for_all_lengths() {
+        for signal_len in 1..=9 {
+            for template_len in 1..=9 {
+                let signal: Vec<f64> = (0..signal_len).map(|i| i as f64 + 1.0).collect();
+                let template: Vec<f64> = (0..template_len).map(|i| 1.0 - i as f64 * 0.3).collect();
+                for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                    let result = fft_correlate_1d_with_lags(&signal, &template, mode).unwrap();
+                    assert_eq!(result.values(), fft_correlate_1d(&signal, &template, mode).unwrap().as_slice());
+                    assert_eq!((result.mode(), result.signal_len(), result.template_len()), (Some(mode), signal_len, template_len));
+                    assert_eq!(result.lag_range(), None);
+                    for (index, lag) in result.lags().into_iter().enumerate() {
+                        assert_eq!(lag, mode.lag_at(index, template_len));
+                        assert_eq!(result.index_of_lag(lag), Some(index));
+                        assert_eq!(mode.index_of_lag(lag, signal_len, template_len), Some(index));
+                    }
+                    let first = result.lag_at(0);
+                    assert_eq!(result.index_of_lag(first - 1), None);
+                    assert_eq!(result.index_of_lag(first + result.len() as isize), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_argmax_lag_finds_delay_and_skips_non_finite() {
+        let template: Vec<f32> = (0..12).map(|i| (i as f32 * 0.8).sin()).collect();
+        let mut signal = vec![0.0f32; 300];
+        signal[171..183].copy_from_slice(&template);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let result = fft_correlate_1d_with_lags(&signal, &template, mode).unwrap();
+            assert_eq!(result.argmax_lag(), Some(171), "{:?}", mode);
+        }
+
+        let result = Correlation::new(vec![1.0f64, f64::NAN, 3.0, f64::INFINITY, 3.0], Mode::Valid, 7, 3);
+        assert_eq!(result.argmax(), Some(2));
+        assert_eq!(result.argmax_lag(), Some(2));
+        let empty = Correlation::<f64>::new(Vec::new(), Mode::Valid, 2, 3);
+        assert!(empty.is_empty() && empty.argmax_lag().is_none());
+    }
+
+    #[test]
+    fn test_lag_range_correlator_reports_its_window() {
+        let signal: Vec<f64> = (0..40).map(|i| (i as f64 * 0.4).cos()).collect();
+        let template = [1.0, -0.5, 0.25];
+        let correlator = Correlator::builder().lag_range(-5..=10).build().unwrap();
+        let result = correlator.correlate_with_lags(&signal, &template).unwrap();
+        assert_eq!((result.mode(), result.lag_range()), (None, Some(-5..=10)));
+        assert_eq!(result.lags(), (-5..=10).collect::<Vec<isize>>());
+        assert_eq!(result.value_at_lag(-5), Some(0.0));
+        assert_eq!(result.index_of_lag(11), None);
+        assert_eq!(result.into_values(), correlator.correlate(&signal, &template).unwrap());
+        let empty = correlator.correlate_with_lags(&[], &template).unwrap();
+        assert!(empty.is_empty() && empty.index_of_lag(-5).is_none());
+        assert_eq!((empty.mode(), empty.lag_range()), (None, Some(-5..=10)));
+    }
+
+    #[test]
+    fn test_extreme_lags_are_not_in_output() {
+        let signal: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let template = [1.0, 2.0, 3.0];
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let result = fft_correlate_1d_with_lags(&signal, &template, mode).unwrap();
+            for lag in [isize::MIN, isize::MIN + 1, isize::MAX - 1, isize::MAX] {
+                assert_eq!(result.index_of_lag(lag), None, "{:?} lag {}", mode, lag);
+                assert_eq!(result.value_at_lag(lag), None, "{:?} lag {}", mode, lag);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "output length")]
+    fn test_new_rejects_mismatched_length() {
+        Correlation::new(vec![0.0f32; 3], Mode::Full, 3, 2);
+    }
+}
@@ -0,0 +1,439 @@
+//! Configurable correlator built from validated options
+//!
+//! The free functions each expose one option on top of `(signal, template, mode)`. A
+//! [`Correlator`] combines them: mode or lag range, algorithm, FFT sizing, normalization,
+//! non-finite policy and strict validation. [`CorrelatorBuilder::build`] rejects contradictory
+//! combinations once, so the resulting correlator can be reused for any number of calls.
+//! [`fft_correlate_1d`](crate::fft_correlate_1d) is the default configuration with a chosen mode.
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+use crate::lag_window::{lag_count, masked_window_values, window_values};
+use crate::masked::{affected_lags, masked_correlate_1d};
+use crate::nonfinite::{finite_masks, zero_non_finite};
+use crate::validate::{check_finite, validate_inputs};
+use crate::{
+    choose_method, direct_correlate_1d, fft_correlate_1d_with_sizing, fft_normalized_correlate_1d, Correlation,
+    FftCorrelationError, FftFloat, FftSizing, Method, Mode, NccKind, NonFinitePolicy, Result,
+};
+
+/// Reusable correlation configuration; create one with [`Correlator::builder`]
+///
+/// The sample precision is the type parameter: `Correlator::<f32>::builder()`.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{Correlator, Method, Mode, NccKind, NonFinitePolicy};
+///
+/// let correlator = Correlator::<f64>::builder()
+///     .mode(Mode::Valid)
+///     .method(Method::Auto)
+///     .normalization(NccKind::Pearson)
+///     .non_finite(NonFinitePolicy::Mask)
+///     .build()
+///     .unwrap();
+///
+/// let template = [1.0, 3.0, 2.0];
+/// let signal = [0.0, 2.0, 6.0, 4.0, 9.0, f64::NAN, 1.0];
+/// let ncc = correlator.correlate(&signal, &template).unwrap();
+/// assert!((ncc[1] - 1.0).abs() < 1e-9);
+/// assert!(ncc[3].is_nan());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Correlator<T> {
+    mode: Mode,
+    method: Method,
+    sizing: FftSizing,
+    normalization: Option<NccKind>,
+    non_finite: NonFinitePolicy,
+    strict: bool,
+    lag_range: Option<(isize, isize)>,
+    precision: PhantomData<fn() -> T>,
+}
+
+/// Builder for [`Correlator`]
+///
+/// Defaults reproduce [`fft_correlate_1d`](crate::fft_correlate_1d) in Full mode:
+/// [`Method::Fft`], [`FftSizing::Fast`], no normalization, [`NonFinitePolicy::Propagate`] and
+/// lenient validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorrelatorBuilder<T> {
+    mode: Option<Mode>,
+    method: Method,
+    sizing: FftSizing,
+    normalization: Option<NccKind>,
+    non_finite: NonFinitePolicy,
+    strict: bool,
+    lag_range: Option<(isize, isize)>,
+    precision: PhantomData<fn() -> T>,
+}
+
+impl<T: FftFloat> Default for CorrelatorBuilder<T> {
+    fn default() -> Self {
+        Self {
+            mode: None,
+            method: Method::Fft,
+            sizing: FftSizing::default(),
+            normalization: None,
+            non_finite: NonFinitePolicy::default(),
+            strict: false,
+            lag_range: None,
+            precision: PhantomData,
+        }
+    }
+}
+
+impl<T: FftFloat> CorrelatorBuilder<T> {
+    /// Output mode; defaults to [`Mode::Full`] and cannot be combined with a lag range
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Correlation algorithm; defaults to [`Method::Fft`]
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Padded FFT length policy for the raw FFT path
+    pub fn sizing(mut self, sizing: FftSizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Return normalized correlation (see
+    /// [`fft_normalized_correlate_1d`](crate::fft_normalized_correlate_1d)) instead of raw sums
+    pub fn normalization(mut self, kind: NccKind) -> Self {
+        self.normalization = Some(kind);
+        self
+    }
+
+    /// Treatment of NaN and infinite samples (see [`NonFinitePolicy`])
+    pub fn non_finite(mut self, policy: NonFinitePolicy) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Reject degenerate inputs with typed errors (see [`validate_inputs`](crate::validate_inputs))
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Return exactly the lags in `lags` instead of a [`Mode`]'s output
+    ///
+    /// Lag `l` means `template[0]` aligns with `signal[l]`, as in [`Mode::lag_at`]; lags without
+    /// overlapping samples are zero. Raw correlation computes only these lags (see
+    /// [`correlate_lag_window`](crate::correlate_lag_window)); normalized correlation is computed
+    /// in full and then trimmed.
+    pub fn lag_range(mut self, lags: RangeInclusive<isize>) -> Self {
+        self.lag_range = Some((*lags.start(), *lags.end()));
+        self
+    }
+
+    /// Check the option combination and create the correlator
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::InvalidLagRange` for an empty lag range or one with more lags
+    /// than `usize` can count, and
+    /// `FftCorrelationError::ConflictingOptions` if
+    ///
+    /// - both a mode and a lag range are set, since each defines the output lags
+    /// - normalization is combined with [`Method::Direct`], as it is only implemented with FFTs
+    /// - strict validation is combined with [`NonFinitePolicy::Zero`] or [`NonFinitePolicy::Mask`],
+    ///   since strict validation rejects the samples those policies would handle
+    pub fn build(self) -> Result<Correlator<T>> {
+        if let Some((start, end)) = self.lag_range {
+            lag_count(start, end)?;
+            if self.mode.is_some() {
+                return Err(FftCorrelationError::ConflictingOptions { first: "mode", second: "lag_range" });
+            }
+        }
+        if self.normalization.is_some() && self.method == Method::Direct {
+            return Err(FftCorrelationError::ConflictingOptions { first: "normalization", second: "Method::Direct" });
+        }
+        if self.strict && matches!(self.non_finite, NonFinitePolicy::Zero | NonFinitePolicy::Mask) {
+            return Err(FftCorrelationError::ConflictingOptions { first: "strict", second: "non_finite" });
+        }
+        Ok(Correlator {
+            mode: self.mode.unwrap_or(Mode::Full),
+            method: self.method,
+            sizing: self.sizing,
+            normalization: self.normalization,
+            non_finite: self.non_finite,
+            strict: self.strict,
+            lag_range: self.lag_range,
+            precision: PhantomData,
+        })
+    }
+}
+
+impl<T: FftFloat> Default for Correlator<T> {
+    fn default() -> Self {
+        CorrelatorBuilder::default().build().expect("default options are consistent")
+    }
+}
+
+impl<T: FftFloat> Correlator<T> {
+    /// Start configuring a correlator
+    pub fn builder() -> CorrelatorBuilder<T> {
+        CorrelatorBuilder::default()
+    }
+
+    /// Output mode (`Mode::Full` when a lag range is set)
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Correlation algorithm
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Padded FFT length policy
+    pub fn sizing(&self) -> FftSizing {
+        self.sizing
+    }
+
+    /// Normalization, if any
+    pub fn normalization(&self) -> Option<NccKind> {
+        self.normalization
+    }
+
+    /// Treatment of NaN and infinite samples
+    pub fn non_finite(&self) -> NonFinitePolicy {
+        self.non_finite
+    }
+
+    /// Whether degenerate inputs are rejected
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Requested output lags, if set
+    pub fn lag_range(&self) -> Option<RangeInclusive<isize>> {
+        self.lag_range.map(|(start, end)| start..=end)
+    }
+
+    /// Correlate `signal` against `template` with this configuration
+    ///
+    /// Output length and indexing follow the configured [`Mode`] exactly as in
+    /// [`fft_correlate_1d`](crate::fft_correlate_1d), or cover the configured lag range one lag
+    /// per sample. Without strict validation, empty inputs (and Valid mode with a template longer
+    /// than the signal) give an empty vector.
+    ///
+    /// Normalization always uses FFTs; with [`Method::Auto`] only raw correlation may run directly.
+    /// Under [`NonFinitePolicy::Mask`], inputs containing non-finite samples also use FFTs.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`validate_inputs`](crate::validate_inputs) under strict validation,
+    /// `FftCorrelationError::NonFinite` under [`NonFinitePolicy::Reject`], and
+    /// `FftCorrelationError::FftProcessing` if FFT processing fails.
+    pub fn correlate(&self, signal: &[T], template: &[T]) -> Result<Vec<T>> {
+        if self.strict {
+            validate_inputs(signal, template, self.mode)?;
+        }
+        if self.non_finite == NonFinitePolicy::Mask {
+            if let Some((signal_mask, template_mask)) = finite_masks(signal, template) {
+                return self.masked(signal, &signal_mask, template, &template_mask);
+            }
+        }
+        let (signal, template) = match self.non_finite {
+            NonFinitePolicy::Propagate | NonFinitePolicy::Mask => (Cow::Borrowed(signal), Cow::Borrowed(template)),
+            NonFinitePolicy::Reject => {
+                check_finite(signal, template)?;
+                (Cow::Borrowed(signal), Cow::Borrowed(template))
+            }
+            NonFinitePolicy::Zero => (zero_non_finite(signal), zero_non_finite(template)),
+        };
+
+        match (self.normalization, self.lag_range) {
+            (None, Some((start, end))) => window_values(&signal, &template, start, end, self.method, self.sizing),
+            (None, None) => self.raw(&signal, &template),
+            (Some(kind), _) => Ok(self.select_lags(
+                fft_normalized_correlate_1d(&signal, &template, self.mode, kind)?,
+                template.len(),
+                T::zero(),
+            )),
+        }
+    }
+
+    /// Correlate like [`correlate`](Self::correlate) and keep the lag metadata
+    ///
+    /// # Errors
+    ///
+    /// Same as [`correlate`](Self::correlate).
+    pub fn correlate_with_lags(&self, signal: &[T], template: &[T]) -> Result<Correlation<T>> {
+        let values = self.correlate(signal, template)?;
+        Ok(match self.lag_range {
+            Some((start, end)) => Correlation::from_lag_range(values, start..=end, signal.len(), template.len()),
+            None => Correlation::new(values, self.mode, signal.len(), template.len()),
+        })
+    }
+
+    // Pick the configured lag range out of a Full output, filling lags without overlap
+    fn select_lags<V: Copy>(&self, full: Vec<V>, template_len: usize, fill: V) -> Vec<V> {
+        match self.lag_range {
+            Some((start, end)) if !full.is_empty() => (start..=end)
+                .map(|lag| {
+                    let index = lag + template_len as isize - 1;
+                    usize::try_from(index).ok().and_then(|k| full.get(k)).copied().unwrap_or(fill)
+                })
+                .collect(),
+            _ => full,
+        }
+    }
+
+    // `NonFinitePolicy::Mask` with non-finite samples present: raw values come from the same
+    // masked correlation as `fft_correlate_1d_with_policy`, restricted to the lag window if one is
+    // set. Normalized values are computed on zero-filled inputs, then affected lags become NaN.
+    fn masked(&self, signal: &[T], signal_mask: &[bool], template: &[T], template_mask: &[bool]) -> Result<Vec<T>> {
+        let kind = match (self.normalization, self.lag_range) {
+            (None, Some((start, end))) => {
+                return masked_window_values(signal, signal_mask, template, template_mask, start, end)
+            }
+            (None, None) => return masked_correlate_1d(signal, signal_mask, template, template_mask, self.mode),
+            (Some(kind), _) => kind,
+        };
+        let normalized = fft_normalized_correlate_1d(&zero_non_finite(signal), &zero_non_finite(template), self.mode, kind)?;
+        let mut values = self.select_lags(normalized, template.len(), T::zero());
+        let affected = self.select_lags(affected_lags(signal_mask, template_mask, self.mode)?, template.len(), false);
+        for (value, _) in values.iter_mut().zip(affected).filter(|&(_, hit)| hit) {
+            *value = T::nan();
+        }
+        Ok(values)
+    }
+
+    // Raw correlation in this correlator's mode with the configured method and sizing
+    fn raw(&self, signal: &[T], template: &[T]) -> Result<Vec<T>> {
+        let method = match self.method {
+            Method::Auto => choose_method(signal.len(), template.len(), self.mode),
+            method => method,
+        };
+        match method {
+            Method::Direct => Ok(direct_correlate_1d(signal, template, self.mode)),
+            _ => fft_correlate_1d_with_sizing(signal, template, self.mode, self.sizing),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_correlate_1d, fft_correlate_1d_with_policy};
+
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (i as f64 * 0.29).sin() + 0.3 * (i as f64 * 1.3).cos()).collect()
+    }
+
+    #[test]
+    fn test_default_matches_fft_correlate_1d() {
+        let signal = test_signal(100);
+        let template = test_signal(9);
+        assert_eq!(Correlator::default().correlate(&signal, &template).unwrap(), fft_correlate_1d(&signal, &template, Mode::Full).unwrap());
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let correlator = Correlator::builder().mode(mode).build().unwrap();
+            assert_eq!(correlator.mode(), mode);
+            assert_eq!(correlator.correlate(&signal, &template).unwrap(), fft_correlate_1d(&signal, &template, mode).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_methods_and_sizing_agree() {
+        let signal = test_signal(300);
+        let template = test_signal(20);
+        let reference = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
+        for method in [Method::Auto, Method::Fft, Method::Direct] {
+            for sizing in [FftSizing::PowerOfTwo, FftSizing::Fast] {
+                let correlator = Correlator::builder().mode(Mode::Same).method(method).sizing(sizing).build().unwrap();
+                let result = correlator.correlate(&signal, &template).unwrap();
+                for (a, b) in result.iter().zip(reference.iter()) {
+                    assert!((a - b).abs() < 1e-10, "{:?} {:?}", method, sizing);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalization_and_non_finite_match_free_functions() {
+        let mut signal = test_signal(80);
+        let template = test_signal(7);
+        let correlator = Correlator::builder().mode(Mode::Valid).normalization(NccKind::Cosine).build().unwrap();
+        assert_eq!(
+            correlator.correlate(&signal, &template).unwrap(),
+            fft_normalized_correlate_1d(&signal, &template, Mode::Valid, NccKind::Cosine).unwrap()
+        );
+
+        signal[33] = f64::NAN;
+        for policy in [NonFinitePolicy::Zero, NonFinitePolicy::Mask] {
+            let correlator = Correlator::builder().mode(Mode::Same).non_finite(policy).build().unwrap();
+            let result = correlator.correlate(&signal, &template).unwrap();
+            let expected = fft_correlate_1d_with_policy(&signal, &template, Mode::Same, policy).unwrap();
+            assert_eq!(result.iter().filter(|v| v.is_nan()).count(), expected.iter().filter(|v| v.is_nan()).count());
+            for (a, b) in result.iter().zip(expected.iter()).filter(|(a, _)| a.is_finite()) {
+                assert!((a - b).abs() < 1e-12, "{:?}: {} vs {}", policy, a, b);
+            }
+        }
+        let strict = Correlator::builder().non_finite(NonFinitePolicy::Reject).strict(true).build().unwrap();
+        assert!(matches!(strict.correlate(&signal, &template), Err(FftCorrelationError::NonFinite { index: 33, .. })));
+        assert!(matches!(strict.correlate(&[], &template), Err(FftCorrelationError::EmptySignal)));
+    }
+
+    #[test]
+    fn test_lag_range_selects_lags_with_zero_padding() {
+        let signal = test_signal(30);
+        let template = test_signal(5);
+        let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+        let correlator = Correlator::builder().lag_range(-8..=31).build().unwrap();
+        assert_eq!(correlator.lag_range(), Some(-8..=31));
+        let result = correlator.correlate(&signal, &template).unwrap();
+        assert_eq!(result.len(), 40);
+        for (k, lag) in (-8..=31).enumerate() {
+            let expected = if (-4..=29).contains(&lag) { full[(lag + 4) as usize] } else { 0.0 };
+            assert!((result[k] - expected).abs() < 1e-12, "Lag {}", lag);
+        }
+    }
+
+    #[test]
+    fn test_masked_lag_range_matches_full_mask_policy() {
+        let mut signal = test_signal(300);
+        signal[40] = f64::NAN;
+        signal[210] = f64::INFINITY;
+        let mut template = test_signal(12);
+        template[3] = f64::NAN;
+        let full = fft_correlate_1d_with_policy(&signal, &template, Mode::Full, NonFinitePolicy::Mask).unwrap();
+        for (start, end) in [(-20, 10), (25, 60), (150, 320), (-11, 299)] {
+            let correlator = Correlator::builder().lag_range(start..=end).non_finite(NonFinitePolicy::Mask).build().unwrap();
+            let result = correlator.correlate(&signal, &template).unwrap();
+            assert_eq!(result.len(), (end - start + 1) as usize);
+            for (value, lag) in result.iter().zip(start..=end) {
+                let expected = if (-11..=299).contains(&lag) { full[(lag + 11) as usize] } else { 0.0 };
+                if expected.is_nan() {
+                    assert!(value.is_nan(), "Lag {}", lag);
+                } else {
+                    assert!((value - expected).abs() < 1e-10, "Lag {}: {} vs {}", lag, value, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_rejects_conflicting_options() {
+        let conflict = |builder: CorrelatorBuilder<f32>| matches!(builder.build(), Err(FftCorrelationError::ConflictingOptions { .. }));
+        assert!(conflict(Correlator::builder().mode(Mode::Same).lag_range(0..=3)));
+        assert!(conflict(Correlator::builder().normalization(NccKind::Pearson).method(Method::Direct)));
+        assert!(conflict(Correlator::builder().strict(true).non_finite(NonFinitePolicy::Mask)));
+        let (start, end) = (3, -3);
+        assert!(matches!(
+            Correlator::<f32>::builder().lag_range(start..=end).build(),
+            Err(FftCorrelationError::InvalidLagRange { start: 3, end: -3 })
+        ));
+        assert!(Correlator::<f32>::builder().strict(true).non_finite(NonFinitePolicy::Reject).build().is_ok());
+    }
+}
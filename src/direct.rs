@@ -0,0 +1,200 @@
+//! Direct (sliding dot product) correlation and automatic method selection
+//!
+//! For short templates a direct sliding dot product beats the FFT path: it avoids padding,
+//! three transforms and the associated allocations, and it is free of FFT round-off. Like
+//! `scipy.signal.correlate(method="auto")`, [`correlate_1d`] estimates the cost of both methods
+//! and picks the cheaper one.
+//!
+//! The direct kernel iterates over template taps and adds each scaled, contiguous slice of the
+//! signal into the output, so the inner loop is a plain `axpy` that the compiler vectorizes.
+
+use crate::{fft_correlate_1d, FftFloat, FftSizing, Mode, Result};
+
+/// Relative cost of one FFT butterfly-level operation versus one direct multiply-add
+///
+/// A heuristic estimate, not a measured constant: a full FFT correlation of padded length `L` is
+/// taken to cost roughly `FFT_COST_FACTOR * L * log2(L)` multiply-add equivalents, including the
+/// two forward transforms, the inverse transform and buffer handling. `cargo bench --bench
+/// method_crossover` times both methods around the switch point; for a 4096-sample signal in
+/// Valid mode the estimate switches to FFT at 38 taps, and the measured crossover on an x86_64
+/// release build lies between 48 and 64 taps.
+const FFT_COST_FACTOR: f64 = 3.0;
+
+/// Correlation algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Method {
+    /// Pick the cheaper of `Fft` and `Direct` from the input sizes and mode
+    #[default]
+    Auto,
+    /// Frequency-domain correlation, O((N + M) log(N + M))
+    Fft,
+    /// Sliding dot product, O(N * M); exact up to summation round-off
+    Direct,
+}
+
+/// Correlate two 1D signals with the chosen method
+///
+/// Output length and indexing follow [`Mode`] exactly as in [`fft_correlate_1d`]; both methods
+/// return the same values up to floating-point round-off. `Method::Auto` resolves through
+/// [`choose_method`].
+///
+/// Returns an empty vector if either input is empty or if Valid mode is used
+/// with signal shorter than template.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{correlate_1d, Method, Mode};
+///
+/// let signal = [1.0f32, 2.0, 3.0, 4.0, 5.0];
+/// let template = [1.0f32, 0.0, -1.0];
+/// let auto = correlate_1d(&signal, &template, Mode::Valid, Method::Auto).unwrap();
+/// assert_eq!(auto, vec![-2.0, -2.0, -2.0]);
+/// ```
+pub fn correlate_1d<T: FftFloat>(signal: &[T], template: &[T], mode: Mode, method: Method) -> Result<Vec<T>> {
+    match method {
+        Method::Auto => match choose_method(signal.len(), template.len(), mode) {
+            Method::Direct => Ok(direct_correlate_1d(signal, template, mode)),
+            _ => fft_correlate_1d(signal, template, mode),
+        },
+        Method::Fft => fft_correlate_1d(signal, template, mode),
+        Method::Direct => Ok(direct_correlate_1d(signal, template, mode)),
+    }
+}
+
+/// Pick the faster correlation method for the given input sizes
+///
+/// Compares the number of multiply-adds of the direct method (only the overlapping samples of
+/// each output lag are counted) with a heuristic `L log2 L` estimate for the FFT method, where
+/// `L` is the padded FFT length. Never returns `Method::Auto`.
+pub fn choose_method(signal_len: usize, template_len: usize, mode: Mode) -> Method {
+    if signal_len == 0 || template_len == 0 {
+        return Method::Direct;
+    }
+
+    let direct_cost = direct_multiply_adds(signal_len, template_len, mode) as f64;
+    let fft_size = FftSizing::default().fft_len(signal_len + template_len - 1) as f64;
+    let fft_cost = FFT_COST_FACTOR * fft_size * fft_size.log2().max(1.0);
+
+    if direct_cost <= fft_cost {
+        Method::Direct
+    } else {
+        Method::Fft
+    }
+}
+
+/// Correlate two 1D signals with a direct sliding dot product
+///
+/// Computes only the lags present in the requested `mode`, with the same indexing as
+/// [`fft_correlate_1d`]. Returns an empty vector if either input is empty or if Valid mode is
+/// used with signal shorter than template.
+pub fn direct_correlate_1d<T: FftFloat>(signal: &[T], template: &[T], mode: Mode) -> Vec<T> {
+    let len = mode.output_len(signal.len(), template.len());
+    let mut output = vec![T::zero(); len];
+    if len == 0 {
+        return output;
+    }
+
+    // Output index j pairs template[i] with signal[j + shift + i]
+    let shift = mode.lag_at(0, template.len());
+    for (i, &tap) in template.iter().enumerate() {
+        let (start, end) = tap_range(shift + i as isize, signal.len(), len);
+        if start >= end {
+            continue;
+        }
+        let offset = (start as isize + shift + i as isize) as usize;
+        for (out, &x) in output[start..end].iter_mut().zip(&signal[offset..offset + (end - start)]) {
+            *out += tap * x;
+        }
+    }
+    output
+}
+
+// Output indices j in [start, end) for which signal[j + offset] exists
+fn tap_range(offset: isize, signal_len: usize, output_len: usize) -> (usize, usize) {
+    let start = (-offset).clamp(0, output_len as isize) as usize;
+    let end = (signal_len as isize - offset).clamp(0, output_len as isize) as usize;
+    (start, end)
+}
+
+// Multiply-adds performed by `direct_correlate_1d`
+fn direct_multiply_adds(signal_len: usize, template_len: usize, mode: Mode) -> usize {
+    let len = mode.output_len(signal_len, template_len);
+    if len == 0 {
+        return 0;
+    }
+    let shift = mode.lag_at(0, template_len);
+    (0..template_len)
+        .map(|i| {
+            let (start, end) = tap_range(shift + i as isize, signal_len, len);
+            end.saturating_sub(start)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vectors(signal_len: usize, template_len: usize) -> (Vec<f64>, Vec<f64>) {
+        let signal = (0..signal_len).map(|i| ((i as f64) * 0.7).cos() + (i as f64) * 0.05).collect();
+        let template = (0..template_len).map(|i| ((i as f64) * 0.4).sin() - (i as f64) * 0.03).collect();
+        (signal, template)
+    }
+
+    #[test]
+    fn test_direct_matches_fft_across_modes() {
+        for signal_len in 0..=20 {
+            for template_len in 0..=20 {
+                let (signal, template) = test_vectors(signal_len, template_len);
+                for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                    let direct = direct_correlate_1d(&signal, &template, mode);
+                    let fft = fft_correlate_1d(&signal, &template, mode).unwrap();
+                    assert_eq!(direct.len(), fft.len(), "Length for {} x {} {:?}", signal_len, template_len, mode);
+                    for (a, b) in direct.iter().zip(fft.iter()) {
+                        assert!((a - b).abs() < 1e-10, "{} x {} {:?}: {} vs {}", signal_len, template_len, mode, a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_direct_is_exact_for_integer_valued_inputs() {
+        // The FFT path leaves round-off on exact integer results; the direct path does not
+        let signal: Vec<f32> = vec![1.0; 50];
+        let result = direct_correlate_1d(&signal, &signal, Mode::Full);
+        assert_eq!(result[49], 50.0);
+        for (k, &v) in result.iter().enumerate() {
+            assert_eq!(v, (50 - (49i32 - k as i32).abs()) as f32);
+        }
+    }
+
+    #[test]
+    fn test_choose_method() {
+        assert_eq!(choose_method(1000, 3, Mode::Full), Method::Direct);
+        assert_eq!(choose_method(100_000, 8, Mode::Valid), Method::Direct);
+        assert_eq!(choose_method(100_000, 4096, Mode::Same), Method::Fft);
+        assert_eq!(choose_method(4096, 4096, Mode::Full), Method::Fft);
+        assert_eq!(choose_method(0, 10, Mode::Full), Method::Direct);
+        // Valid mode with a near-equal-length template has few lags to compute
+        assert_eq!(choose_method(4096, 4090, Mode::Valid), Method::Direct);
+    }
+
+    #[test]
+    fn test_correlate_1d_methods_agree() {
+        let (signal, template) = test_vectors(300, 40);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let fft = correlate_1d(&signal, &template, mode, Method::Fft).unwrap();
+            let direct = correlate_1d(&signal, &template, mode, Method::Direct).unwrap();
+            let auto = correlate_1d(&signal, &template, mode, Method::Auto).unwrap();
+            for ((a, b), c) in fft.iter().zip(direct.iter()).zip(auto.iter()) {
+                assert!((a - b).abs() < 1e-10 && (a - c).abs() < 1e-10);
+            }
+        }
+    }
+}
@@ -1,14 +1,105 @@
 use std::fmt;
 
+/// Which correlation input an error refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    /// The signal being searched
+    Signal,
+    /// The template searched for
+    Template,
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputKind::Signal => write!(f, "signal"),
+            InputKind::Template => write!(f, "template"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum FftCorrelationError {
     FftProcessing(String),
+    /// Streaming block size cannot hold the template plus at least one new sample
+    InvalidBlockSize { block_size: usize, template_len: usize },
+    /// Input slice length does not match the product of its declared dimensions, or a batch has a
+    /// different number of templates than signals
+    ShapeMismatch { expected: usize, actual: usize },
+    /// Correlation axis is out of range for the array rank or listed twice
+    InvalidAxis { axis: usize, ndim: usize },
+    /// Shapes differ in rank, or differ on a non-correlated axis where neither size is 1
+    IncompatibleShapes { signal: Vec<usize>, template: Vec<usize> },
+    /// Channel count is zero or does not divide the multichannel buffer length
+    InvalidChannelCount { channels: usize, len: usize },
+    /// Validity mask length differs from the length of the data it masks
+    MaskLengthMismatch { data_len: usize, mask_len: usize },
+    /// Signal has no samples (strict validation only)
+    EmptySignal,
+    /// Template has no samples (strict validation only)
+    EmptyTemplate,
+    /// Valid mode with a template longer than the signal leaves no fully overlapping lag
+    TemplateLongerThanSignal { signal_len: usize, template_len: usize },
+    /// Sample `index` of `input` is NaN or infinite
+    NonFinite { input: InputKind, index: usize },
+    /// Padded FFT buffers for these lengths would exceed the addressable size
+    SizeOverflow { signal_len: usize, template_len: usize },
+    /// Two correlator options that cannot be used together
+    ConflictingOptions { first: &'static str, second: &'static str },
+    /// Lag range whose start lies after its end, or with more lags than can be allocated
+    InvalidLagRange { start: isize, end: isize },
 }
 
 impl fmt::Display for FftCorrelationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             FftCorrelationError::FftProcessing(msg) => write!(f, "FFT processing error: {}", msg),
+            FftCorrelationError::InvalidBlockSize { block_size, template_len } => write!(
+                f,
+                "block size {} must be at least the template length {}",
+                block_size, template_len
+            ),
+            FftCorrelationError::ShapeMismatch { expected, actual } => write!(
+                f,
+                "input has {} elements where its shape describes {}",
+                actual, expected
+            ),
+            FftCorrelationError::InvalidAxis { axis, ndim } => {
+                write!(f, "axis {} is out of range or repeated for {}-dimensional input", axis, ndim)
+            }
+            FftCorrelationError::IncompatibleShapes { signal, template } => write!(
+                f,
+                "signal shape {:?} and template shape {:?} cannot be broadcast over the non-correlated axes",
+                signal, template
+            ),
+            FftCorrelationError::InvalidChannelCount { channels, len } => {
+                write!(f, "buffer of {} samples cannot be split into {} channels", len, channels)
+            }
+            FftCorrelationError::MaskLengthMismatch { data_len, mask_len } => {
+                write!(f, "mask of length {} does not match data of length {}", mask_len, data_len)
+            }
+            FftCorrelationError::EmptySignal => write!(f, "signal is empty"),
+            FftCorrelationError::EmptyTemplate => write!(f, "template is empty"),
+            FftCorrelationError::TemplateLongerThanSignal { signal_len, template_len } => write!(
+                f,
+                "template of length {} is longer than signal of length {} in Valid mode",
+                template_len, signal_len
+            ),
+            FftCorrelationError::NonFinite { input, index } => write!(f, "{} sample {} is not finite", input, index),
+            FftCorrelationError::SizeOverflow { signal_len, template_len } => write!(
+                f,
+                "correlating {} by {} samples exceeds the addressable FFT size",
+                signal_len, template_len
+            ),
+            FftCorrelationError::ConflictingOptions { first, second } => {
+                write!(f, "options {} and {} cannot be combined", first, second)
+            }
+            FftCorrelationError::InvalidLagRange { start, end } if start > end => {
+                write!(f, "lag range {}..={} is empty", start, end)
+            }
+            FftCorrelationError::InvalidLagRange { start, end } => {
+                write!(f, "lag range {}..={} has too many lags to allocate", start, end)
+            }
         }
     }
 }
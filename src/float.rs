@@ -0,0 +1,69 @@
+//! Floating-point precisions supported by the correlation routines
+//!
+//! All correlation functions are generic over [`FftFloat`], which is implemented for `f32` and
+//! `f64`. Each precision keeps its own thread-local FFT planner caches (real and complex), so plans
+//! for one precision never evict or contend with plans for the other.
+
+use realfft::num_traits::{Float, NumAssign};
+use realfft::{FftNum, RealFftPlanner};
+use rustfft::FftPlanner;
+use std::cell::RefCell;
+
+// Thread-local FFT planner caches for optimal performance, one per precision
+thread_local! {
+    static FFT_PLANNER_F32: RefCell<RealFftPlanner<f32>> = RefCell::new(RealFftPlanner::new());
+    static FFT_PLANNER_F64: RefCell<RealFftPlanner<f64>> = RefCell::new(RealFftPlanner::new());
+    static COMPLEX_PLANNER_F32: RefCell<FftPlanner<f32>> = RefCell::new(FftPlanner::new());
+    static COMPLEX_PLANNER_F64: RefCell<FftPlanner<f64>> = RefCell::new(FftPlanner::new());
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Sample type accepted by the correlation functions (`f32` or `f64`)
+///
+/// This trait is sealed; it cannot be implemented outside this crate.
+pub trait FftFloat: FftNum + Float + NumAssign + private::Sealed {
+    /// Run `f` with this precision's thread-local planner
+    #[doc(hidden)]
+    fn with_planner<R>(f: impl FnOnce(&mut RealFftPlanner<Self>) -> R) -> R;
+
+    /// Run `f` with this precision's thread-local complex FFT planner
+    #[doc(hidden)]
+    fn with_complex_planner<R>(f: impl FnOnce(&mut FftPlanner<Self>) -> R) -> R;
+
+    /// Convert a length or count to this precision
+    #[doc(hidden)]
+    fn from_len(len: usize) -> Self;
+}
+
+impl FftFloat for f32 {
+    fn with_planner<R>(f: impl FnOnce(&mut RealFftPlanner<Self>) -> R) -> R {
+        FFT_PLANNER_F32.with(|planner_cell| f(&mut planner_cell.borrow_mut()))
+    }
+
+    fn with_complex_planner<R>(f: impl FnOnce(&mut FftPlanner<Self>) -> R) -> R {
+        COMPLEX_PLANNER_F32.with(|planner_cell| f(&mut planner_cell.borrow_mut()))
+    }
+
+    fn from_len(len: usize) -> Self {
+        len as f32
+    }
+}
+
+impl FftFloat for f64 {
+    fn with_planner<R>(f: impl FnOnce(&mut RealFftPlanner<Self>) -> R) -> R {
+        FFT_PLANNER_F64.with(|planner_cell| f(&mut planner_cell.borrow_mut()))
+    }
+
+    fn with_complex_planner<R>(f: impl FnOnce(&mut FftPlanner<Self>) -> R) -> R {
+        COMPLEX_PLANNER_F64.with(|planner_cell| f(&mut planner_cell.borrow_mut()))
+    }
+
+    fn from_len(len: usize) -> Self {
+        len as f64
+    }
+}
@@ -0,0 +1,314 @@
+//! Generalized cross-correlation (GCC) with frequency-domain weighting
+//!
+//! Plain correlation multiplies the two spectra directly, so strongly coloured or reverberant
+//! signals give broad peaks. GCC applies a real weighting `psi(f)` to the cross-spectrum before
+//! the inverse FFT:
+//!
+//! ```text
+//! r(lag) = IFFT( psi(f) * X(f) * conj(Y(f)) )
+//! ```
+//!
+//! where `X` is the signal spectrum and `Y` the template spectrum. The weightings follow
+//! Knapp & Carter, "The generalized correlation method for estimation of time delay" (1976).
+//! Padding, plan caching and [`Mode`] trimming are shared with
+//! [`fft_correlate_1d`](crate::fft_correlate_1d), so lags and output indices line up exactly.
+//!
+//! SCOT, Eckart and ML weightings depend on auto- and cross-spectral densities. From a single
+//! pair of finite recordings those are estimated by averaging neighbouring frequency bins (see
+//! [`GccOptions::smoothing_bins`]); without smoothing the estimated coherence is identically one
+//! and Eckart and ML degenerate. The cross-spectrum is smoothed by magnitude, so the estimates do
+//! not depend on the delay being measured.
+
+use crate::{
+    inverse_to_output, padded_spectrum, plan_fft, reversed_template_spectrum, FftFloat, FftSizing, Mode, Result,
+};
+
+/// Frequency weighting applied to the cross-spectrum
+///
+/// `|Gxy|` is the cross-spectrum magnitude, `Gxx` the signal auto-spectrum and `Gyy` the template
+/// auto-spectrum, all after optional smoothing. `gamma^2 = |Gxy|^2 / (Gxx * Gyy)` is the
+/// magnitude-squared coherence, which only drops below one when smoothing is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GccWeighting {
+    /// `1`: plain cross-correlation, identical to `fft_correlate_1d`
+    Unweighted,
+    /// Phase transform, `1 / |Gxy|`: whitens the cross-spectrum for a sharp peak
+    Phat,
+    /// Smoothed coherence transform, `1 / sqrt(Gxx * Gyy)`
+    Scot,
+    /// Roth processor, `1 / Gxx`: whitens by the signal spectrum only
+    Roth,
+    /// Eckart filter, `|Gxy| / ((Gxx - |Gxy|) * (Gyy - |Gxy|))`, estimating signal and noise
+    /// spectra from the coherent and incoherent parts
+    Eckart,
+    /// Maximum-likelihood (Hannan-Thomson) weighting, `gamma^2 / (|Gxy| * (1 - gamma^2))`
+    Ml,
+}
+
+/// Configuration for [`fft_gcc_1d`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GccOptions {
+    /// Weighting applied to the cross-spectrum
+    pub weighting: GccWeighting,
+    /// Half-width of the moving average over frequency bins used to estimate spectral densities
+    ///
+    /// `0` uses the raw single-snapshot spectra. SCOT, Eckart and ML need a few bins of smoothing
+    /// to obtain a meaningful coherence estimate.
+    pub smoothing_bins: usize,
+}
+
+impl GccOptions {
+    /// Options for `weighting` without spectral smoothing
+    pub fn new(weighting: GccWeighting) -> Self {
+        Self {
+            weighting,
+            smoothing_bins: 0,
+        }
+    }
+
+    /// Set the smoothing half-width in frequency bins
+    pub fn with_smoothing(mut self, smoothing_bins: usize) -> Self {
+        self.smoothing_bins = smoothing_bins;
+        self
+    }
+}
+
+/// Generalized cross-correlation of two 1D signals using FFT
+///
+/// Output length and indexing follow [`Mode`] exactly as in
+/// [`fft_correlate_1d`](crate::fft_correlate_1d), so [`Mode::lag_at`] converts peak indices to
+/// delays. Weighting denominators are floored at `T::epsilon()` times their largest value to
+/// avoid amplifying empty frequency bins.
+///
+/// Returns an empty vector if either input is empty or if Valid mode is used
+/// with signal shorter than template.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_gcc_1d, find_max_peak, GccOptions, GccWeighting, Mode};
+///
+/// let reference: Vec<f64> = (0..256).map(|i| ((i * i) as f64 * 0.01).sin()).collect();
+/// let mut delayed = vec![0.0; 256];
+/// delayed[17..].copy_from_slice(&reference[..239]);
+///
+/// let options = GccOptions::new(GccWeighting::Phat);
+/// let gcc = fft_gcc_1d(&delayed, &reference, Mode::Full, &options).unwrap();
+/// let peak = find_max_peak(&gcc, reference.len(), Mode::Full).unwrap();
+/// assert_eq!(peak.lag, 17);
+/// ```
+pub fn fft_gcc_1d<T: FftFloat>(signal: &[T], template: &[T], mode: Mode, options: &GccOptions) -> Result<Vec<T>> {
+    if signal.is_empty() || template.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output_len = signal.len() + template.len() - 1;
+    let fft_size = FftSizing::default().fft_len(output_len);
+    let (r2c, c2r) = plan_fft(fft_size);
+
+    let template_spectrum = reversed_template_spectrum(template, fft_size, r2c.as_ref())?;
+    let mut cross = padded_spectrum(signal, fft_size, r2c.as_ref())?;
+
+    // The reversed template spectrum has the same magnitude as the template spectrum, and its
+    // product with the signal spectrum is the cross-spectrum up to a linear phase, which the
+    // real weights below leave untouched
+    let signal_power: Vec<T> = cross.iter().map(|x| x.norm_sqr()).collect();
+    let template_power: Vec<T> = template_spectrum.iter().map(|y| y.norm_sqr()).collect();
+    for (x, y) in cross.iter_mut().zip(template_spectrum.iter()) {
+        *x *= y;
+    }
+
+    if options.weighting != GccWeighting::Unweighted {
+        // |Gxy| is smoothed as a magnitude: averaging the complex cross-spectrum would cancel
+        // its delay-dependent phase rotation and bias the estimate towards zero for large lags
+        let cross_magnitude: Vec<T> = cross.iter().map(|c| c.norm()).collect();
+        let cross_magnitude = smooth(&cross_magnitude, options.smoothing_bins);
+        let signal_power = smooth(&signal_power, options.smoothing_bins);
+        let template_power = smooth(&template_power, options.smoothing_bins);
+
+        let weights = weights(options.weighting, &cross_magnitude, &signal_power, &template_power);
+        for (c, w) in cross.iter_mut().zip(weights) {
+            *c = c.scale(w);
+        }
+    }
+
+    inverse_to_output(cross, fft_size, c2r.as_ref(), signal.len(), template.len(), mode)
+}
+
+// Per-bin weights as numerator / denominator, with denominators floored relative to their maximum
+fn weights<T: FftFloat>(weighting: GccWeighting, gxy: &[T], gxx: &[T], gyy: &[T]) -> Vec<T> {
+    let one = T::one();
+    let (numerators, denominators): (Vec<T>, Vec<T>) = (0..gxy.len())
+        .map(|k| {
+            let (cxy, cxx, cyy) = (gxy[k], gxx[k], gyy[k]);
+            match weighting {
+                GccWeighting::Unweighted => (one, one),
+                GccWeighting::Phat => (one, cxy),
+                GccWeighting::Scot => (one, (cxx * cyy).sqrt()),
+                GccWeighting::Roth => (one, cxx),
+                GccWeighting::Eckart => (cxy, (cxx - cxy).max(T::zero()) * (cyy - cxy).max(T::zero())),
+                GccWeighting::Ml => {
+                    let coherence = (cxy * cxy / (cxx * cyy)).min(one);
+                    let coherence = if coherence.is_finite() { coherence } else { T::zero() };
+                    (coherence, cxy * (one - coherence))
+                }
+            }
+        })
+        .unzip();
+
+    let max_denominator = denominators.iter().cloned().fold(T::zero(), T::max);
+    let floor = max_denominator * T::epsilon();
+    numerators
+        .into_iter()
+        .zip(denominators)
+        .map(|(n, d)| if floor > T::zero() { n / d.max(floor) } else { T::zero() })
+        .collect()
+}
+
+// Centered moving average over +/- half_width bins, truncated at the spectrum edges
+fn smooth<T: FftFloat>(values: &[T], half_width: usize) -> Vec<T> {
+    if half_width == 0 {
+        return values.to_vec();
+    }
+    let mut prefix = Vec::with_capacity(values.len() + 1);
+    prefix.push(T::zero());
+    for &v in values {
+        prefix.push(*prefix.last().unwrap() + v);
+    }
+    (0..values.len())
+        .map(|k| {
+            let start = k.saturating_sub(half_width);
+            let end = (k + half_width + 1).min(values.len());
+            (prefix[end] - prefix[start]) / T::from_len(end - start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_correlate_1d, find_max_peak};
+
+    // Deterministic pseudo-random noise in [-1, 1)
+    fn noise(len: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((state >> 11) as f64 / (1u64 << 53) as f64) * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    // Strongly low-passed noise with a delayed copy, an echo and independent sensor noise,
+    // to mimic a reverberant channel
+    fn reverberant_pair(delay: usize) -> (Vec<f64>, Vec<f64>) {
+        let raw = noise(1200, 7);
+        let mut source = vec![0.0; raw.len()];
+        let mut state = 0.0;
+        for (out, &x) in source.iter_mut().zip(raw.iter()) {
+            state = 0.9 * state + x;
+            *out = state;
+        }
+        let reference = source[100..1100].to_vec();
+        let sensor_noise = noise(1000, 11);
+        let received = (0..1000)
+            .map(|i| source[100 + i - delay] + 0.6 * source[100 + i - delay - 9] + 0.5 * sensor_noise[i])
+            .collect();
+        (received, reference)
+    }
+
+    #[test]
+    fn test_gcc_unweighted_matches_plain_correlation() {
+        let signal = noise(100, 1);
+        let template = noise(30, 2);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let plain = fft_correlate_1d(&signal, &template, mode).unwrap();
+            let gcc = fft_gcc_1d(&signal, &template, mode, &GccOptions::new(GccWeighting::Unweighted)).unwrap();
+            assert_eq!(gcc.len(), plain.len());
+            for (a, b) in gcc.iter().zip(plain.iter()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gcc_weightings_find_delay_in_reverberant_signal() {
+        let delay = 23;
+        let (received, reference) = reverberant_pair(delay);
+
+        for weighting in [GccWeighting::Unweighted, GccWeighting::Phat, GccWeighting::Scot, GccWeighting::Roth] {
+            let options = GccOptions::new(weighting).with_smoothing(4);
+            let gcc = fft_gcc_1d(&received, &reference, Mode::Full, &options).unwrap();
+            assert_eq!(gcc.len(), received.len() + reference.len() - 1);
+            let peak = find_max_peak(&gcc, reference.len(), Mode::Full).unwrap();
+            assert_eq!(peak.lag, delay as isize, "{:?} found lag {}", weighting, peak.lag);
+        }
+    }
+
+    #[test]
+    fn test_gcc_coherence_weightings_find_delay_in_noise() {
+        // Eckart and ML assume uncorrelated noise on both channels rather than reverberation
+        let delay = 41;
+        let source = noise(1100, 5);
+        let noise_a = noise(1000, 17);
+        let noise_b = noise(1000, 29);
+        let reference: Vec<f64> = (0..1000).map(|i| source[100 + i] + 0.7 * noise_a[i]).collect();
+        let received: Vec<f64> = (0..1000).map(|i| source[100 + i - delay] + 0.7 * noise_b[i]).collect();
+
+        for weighting in [GccWeighting::Scot, GccWeighting::Eckart, GccWeighting::Ml] {
+            let options = GccOptions::new(weighting).with_smoothing(8);
+            let gcc = fft_gcc_1d(&received, &reference, Mode::Full, &options).unwrap();
+            let peak = find_max_peak(&gcc, reference.len(), Mode::Full).unwrap();
+            assert_eq!(peak.lag, delay as isize, "{:?} found lag {}", weighting, peak.lag);
+        }
+    }
+
+    #[test]
+    fn test_phat_sharpens_peak() {
+        let (received, reference) = reverberant_pair(23);
+
+        // Fraction of energy within +/-2 samples of the peak
+        let concentration = |values: &[f64]| {
+            let peak = find_max_peak(values, reference.len(), Mode::Full).unwrap();
+            let total: f64 = values.iter().map(|v| v * v).sum();
+            let near: f64 = values[peak.index - 2..=peak.index + 2].iter().map(|v| v * v).sum();
+            near / total
+        };
+
+        let plain = fft_gcc_1d(&received, &reference, Mode::Full, &GccOptions::new(GccWeighting::Unweighted)).unwrap();
+        let phat = fft_gcc_1d(&received, &reference, Mode::Full, &GccOptions::new(GccWeighting::Phat)).unwrap();
+        assert!(
+            concentration(&phat) > 2.0 * concentration(&plain),
+            "PHAT concentration {} vs plain {}",
+            concentration(&phat),
+            concentration(&plain)
+        );
+    }
+
+    #[test]
+    fn test_gcc_f32_and_empty_inputs() {
+        let reference: Vec<f32> = noise(128, 3).iter().map(|&v| v as f32).collect();
+        let mut delayed = vec![0.0f32; 160];
+        delayed[11..139].copy_from_slice(&reference);
+
+        let gcc = fft_gcc_1d(&delayed, &reference, Mode::Valid, &GccOptions::new(GccWeighting::Phat)).unwrap();
+        assert_eq!(gcc.len(), 160 - 128 + 1);
+        assert_eq!(find_max_peak(&gcc, reference.len(), Mode::Valid).unwrap().lag, 11);
+
+        let options = GccOptions::new(GccWeighting::Roth);
+        assert!(fft_gcc_1d::<f32>(&[], &[1.0], Mode::Full, &options).unwrap().is_empty());
+        assert!(fft_gcc_1d::<f32>(&[1.0], &[], Mode::Full, &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gcc_all_zero_input_is_finite() {
+        let options = GccOptions::new(GccWeighting::Ml).with_smoothing(2);
+        let gcc = fft_gcc_1d(&[0.0f64; 16], &[0.0; 4], Mode::Full, &options).unwrap();
+        assert!(gcc.iter().all(|v| *v == 0.0));
+    }
+}
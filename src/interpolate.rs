@@ -0,0 +1,242 @@
+//! Sub-sample peak interpolation for time-delay estimation
+//!
+//! Correlation output is sampled at integer lags, but the true delay between two recordings is
+//! usually fractional. These functions refine an integer peak (for example from
+//! [`find_max_peak`](crate::find_max_peak)) to a fractional position:
+//!
+//! - [`Interpolation::Parabolic`] fits a parabola through the peak and its two neighbours.
+//! - [`Interpolation::Gaussian`] fits a parabola to the logarithm of those samples, which is exact
+//!   for Gaussian-shaped peaks and less biased than the plain parabola for typical pulses.
+//! - [`Interpolation::Sinc`] maximizes the band-limited (windowed sinc) reconstruction of the
+//!   correlation around the peak, which is the most accurate choice for band-limited signals.
+
+use std::f64::consts::PI;
+
+use crate::{FftFloat, Mode};
+
+/// Half-width, in samples, of the windowed sinc kernel used by [`Interpolation::Sinc`]
+const SINC_HALF_WIDTH: usize = 32;
+
+/// Golden-section iterations for [`Interpolation::Sinc`]; shrinks the search interval below 1e-12
+const SINC_SEARCH_ITERATIONS: usize = 64;
+
+/// Peak interpolation method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Three-point parabolic fit
+    Parabolic,
+    /// Three-point parabolic fit of the log values; falls back to parabolic when any of the
+    /// three samples is not positive
+    Gaussian,
+    /// Maximum of the Hann-windowed sinc reconstruction within one sample of the peak
+    Sinc,
+}
+
+/// Refine the peak at `index` to a fractional output index
+///
+/// The returned position is in output-index units, so `index + 0.25` means a quarter sample to the
+/// right of `values[index]`. Parabolic and Gaussian offsets are clamped to half a sample; sinc
+/// refinement searches up to one sample on either side.
+///
+/// Peaks at either end of `values`, or next to a non-finite sample, cannot be interpolated and are
+/// returned unrefined. Returns `None` if `index` is out of bounds or `values[index]` is not finite.
+pub fn refine_peak<T: FftFloat>(values: &[T], index: usize, method: Interpolation) -> Option<f64> {
+    let peak = values.get(index)?.to_f64()?;
+    if !peak.is_finite() {
+        return None;
+    }
+    if index == 0 || index + 1 >= values.len() {
+        return Some(index as f64);
+    }
+    let left = values[index - 1].to_f64()?;
+    let right = values[index + 1].to_f64()?;
+    if !left.is_finite() || !right.is_finite() {
+        return Some(index as f64);
+    }
+
+    let offset = match method {
+        Interpolation::Parabolic => parabolic_offset(left, peak, right),
+        Interpolation::Gaussian => {
+            if left > 0.0 && peak > 0.0 && right > 0.0 {
+                parabolic_offset(left.ln(), peak.ln(), right.ln())
+            } else {
+                parabolic_offset(left, peak, right)
+            }
+        }
+        Interpolation::Sinc => return Some(sinc_peak(values, index)),
+    };
+    Some(index as f64 + offset)
+}
+
+/// Refine the peak at `index` to a fractional lag
+///
+/// The result uses the same lag convention as [`Mode::lag_at`]: lag `l` means `template[0]`
+/// aligns with `signal[l]`, so a positive lag means the template appears `l` samples into the
+/// signal. `template_len` and `mode` must be the ones that produced `values`.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d, find_max_peak, subsample_lag, Interpolation, Mode};
+///
+/// // Gaussian pulse delayed by 12.3 samples
+/// let pulse = |c: f64| (0..64).map(move |i| (-(i as f64 - c).powi(2) / 18.0).exp()).collect::<Vec<_>>();
+/// let template = pulse(20.0);
+/// let signal = pulse(32.3);
+///
+/// let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+/// let peak = find_max_peak(&result, template.len(), Mode::Full).unwrap();
+/// let lag = subsample_lag(&result, peak.index, template.len(), Mode::Full, Interpolation::Gaussian).unwrap();
+/// assert!((lag - 12.3).abs() < 1e-3);
+/// ```
+pub fn subsample_lag<T: FftFloat>(
+    values: &[T],
+    index: usize,
+    template_len: usize,
+    mode: Mode,
+    method: Interpolation,
+) -> Option<f64> {
+    let position = refine_peak(values, index, method)?;
+    Some(mode.lag_at(index, template_len) as f64 + (position - index as f64))
+}
+
+// Vertex of the parabola through (-1, y0), (0, y1), (1, y2), clamped to half a sample
+fn parabolic_offset(y0: f64, y1: f64, y2: f64) -> f64 {
+    let denominator = y0 - 2.0 * y1 + y2;
+    if denominator >= 0.0 {
+        // Not concave: no interior maximum to refine towards
+        return 0.0;
+    }
+    (0.5 * (y0 - y2) / denominator).clamp(-0.5, 0.5)
+}
+
+// Band-limited reconstruction at fractional position t using a Hann-windowed sinc kernel
+fn sinc_value<T: FftFloat>(values: &[T], t: f64) -> f64 {
+    let center = t.round() as isize;
+    let half_width = SINC_HALF_WIDTH as isize;
+    let start = (center - half_width).max(0);
+    let end = (center + half_width).min(values.len() as isize - 1);
+
+    (start..=end)
+        .filter_map(|n| {
+            let v = values[n as usize].to_f64().filter(|v| v.is_finite())?;
+            let x = t - n as f64;
+            let window = 0.5 * (1.0 + (PI * x / (SINC_HALF_WIDTH as f64 + 1.0)).cos());
+            let sinc = if x.abs() < 1e-12 { 1.0 } else { (PI * x).sin() / (PI * x) };
+            Some(v * sinc * window)
+        })
+        .sum()
+}
+
+// Golden-section search for the reconstruction maximum in [index - 1, index + 1]
+fn sinc_peak<T: FftFloat>(values: &[T], index: usize) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let mut lo = index as f64 - 1.0;
+    let mut hi = index as f64 + 1.0;
+    let mut a = hi - ratio * (hi - lo);
+    let mut b = lo + ratio * (hi - lo);
+    let mut fa = sinc_value(values, a);
+    let mut fb = sinc_value(values, b);
+
+    for _ in 0..SINC_SEARCH_ITERATIONS {
+        if fa < fb {
+            lo = a;
+            a = b;
+            fa = fb;
+            b = lo + ratio * (hi - lo);
+            fb = sinc_value(values, b);
+        } else {
+            hi = b;
+            b = a;
+            fb = fa;
+            a = hi - ratio * (hi - lo);
+            fa = sinc_value(values, a);
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_correlate_1d, find_max_peak};
+
+    fn gaussian_pulse(len: usize, center: f64, sigma: f64) -> Vec<f64> {
+        (0..len).map(|i| (-(i as f64 - center).powi(2) / (2.0 * sigma * sigma)).exp()).collect()
+    }
+
+    fn estimate_delay(signal: &[f64], template: &[f64], mode: Mode, method: Interpolation) -> f64 {
+        let result = fft_correlate_1d(signal, template, mode).unwrap();
+        let peak = find_max_peak(&result, template.len(), mode).unwrap();
+        subsample_lag(&result, peak.index, template.len(), mode, method).unwrap()
+    }
+
+    #[test]
+    fn test_recovers_fractional_delays() {
+        let template = gaussian_pulse(128, 40.0, 4.0);
+        for delay in [0.0, 0.1, 0.25, 0.5, 0.73, 7.4, -3.6, 21.9] {
+            let signal = gaussian_pulse(128, 40.0 + delay, 4.0);
+            for mode in [Mode::Full, Mode::Same] {
+                let gaussian = estimate_delay(&signal, &template, mode, Interpolation::Gaussian);
+                let sinc = estimate_delay(&signal, &template, mode, Interpolation::Sinc);
+                let parabolic = estimate_delay(&signal, &template, mode, Interpolation::Parabolic);
+
+                assert!((gaussian - delay).abs() < 1e-6, "Gaussian {} vs {} ({:?})", gaussian, delay, mode);
+                assert!((sinc - delay).abs() < 1e-3, "Sinc {} vs {} ({:?})", sinc, delay, mode);
+                assert!((parabolic - delay).abs() < 0.1, "Parabolic {} vs {} ({:?})", parabolic, delay, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recovers_delay_of_band_limited_noise_like_signal() {
+        // Sum of in-band sinusoids, shifted analytically by a fractional delay
+        let freqs = [0.031, 0.077, 0.12, 0.19, 0.23];
+        let phases = [0.3, 1.9, 4.0, 2.2, 5.1];
+        let make = |delay: f64| -> Vec<f64> {
+            (0..512)
+                .map(|i| {
+                    let t = i as f64 - delay;
+                    let envelope = (-(t - 256.0).powi(2) / (2.0 * 60.0 * 60.0)).exp();
+                    envelope * freqs.iter().zip(phases.iter()).map(|(f, p)| (2.0 * PI * f * t + p).sin()).sum::<f64>()
+                })
+                .collect()
+        };
+        let template = make(0.0);
+        let signal = make(5.37);
+
+        let sinc = estimate_delay(&signal, &template, Mode::Full, Interpolation::Sinc);
+        let parabolic = estimate_delay(&signal, &template, Mode::Full, Interpolation::Parabolic);
+        assert!((sinc - 5.37).abs() < 0.01, "Sinc estimate {}", sinc);
+        assert!((sinc - 5.37).abs() < (parabolic - 5.37).abs(), "Sinc {} should beat parabolic {}", sinc, parabolic);
+    }
+
+    #[test]
+    fn test_refine_peak_edges_and_invalid() {
+        let values: Vec<f32> = vec![3.0, 1.0, 2.0, f32::NAN, 0.5, 4.0];
+        assert_eq!(refine_peak(&values, 0, Interpolation::Parabolic), Some(0.0));
+        assert_eq!(refine_peak(&values, 5, Interpolation::Gaussian), Some(5.0));
+        assert_eq!(refine_peak(&values, 2, Interpolation::Parabolic), Some(2.0));
+        assert_eq!(refine_peak(&values, 3, Interpolation::Parabolic), None);
+        assert_eq!(refine_peak(&values, 6, Interpolation::Sinc), None);
+    }
+
+    #[test]
+    fn test_gaussian_falls_back_to_parabolic_for_non_positive() {
+        let values: Vec<f64> = vec![-1.0, 2.0, 1.0];
+        let gaussian = refine_peak(&values, 1, Interpolation::Gaussian).unwrap();
+        let parabolic = refine_peak(&values, 1, Interpolation::Parabolic).unwrap();
+        assert_eq!(gaussian, parabolic);
+        assert!((parabolic - 1.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_subsample_lag_valid_mode_f32() {
+        let template: Vec<f32> = gaussian_pulse(32, 16.0, 3.0).iter().map(|&v| v as f32).collect();
+        let signal: Vec<f32> = gaussian_pulse(200, 116.6, 3.0).iter().map(|&v| v as f32).collect();
+        let result = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+        let peak = find_max_peak(&result, template.len(), Mode::Valid).unwrap();
+        let lag = subsample_lag(&result, peak.index, template.len(), Mode::Valid, Interpolation::Gaussian).unwrap();
+        assert!((lag - 100.6).abs() < 1e-2, "Lag {}", lag);
+    }
+}
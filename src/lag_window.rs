@@ -0,0 +1,388 @@
+//! Correlation restricted to a window of lags
+//!
+//! Time-delay searches often only need lags within a few hundred samples of zero, while the
+//! signals run to millions of samples. [`correlate_lag_window`] computes only the requested lags:
+//! lag `l` depends on `signal[l..l + M]`, so a window of `W` lags needs just `W + M - 1` signal
+//! samples, correlated against the template in Valid mode.
+//!
+//! Long windows are split into overlap-save blocks: every block is one FFT length `L`, a small
+//! multiple of the template length, and yields `L - M + 1` lags. The reversed template spectrum is
+//! computed once for `L` and reused by every block, so each block costs one forward and one
+//! inverse FFT. Blocks run directly instead when [`choose_method`](crate::choose_method)
+//! estimates that to be cheaper. With the `rayon` feature blocks run in parallel.
+
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::masked::masked_segment_correlate_1d;
+use crate::{
+    choose_method, correlate_with_template_spectrum, direct_correlate_1d, plan_fft, reversed_template_spectrum,
+    FftCorrelationError, FftFloat, FftSizing, Method, Mode, Result,
+};
+
+/// Padded FFT length of one block, as a multiple of the template length
+const BLOCK_FFT_FACTOR: usize = 8;
+
+/// Smallest number of lags per block, so short templates do not produce tiny blocks
+const MIN_BLOCK_LAGS: usize = 4096;
+
+/// Window of lags for [`correlate_lag_window`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LagWindow {
+    /// Signed lags `start..=end`, where lag `l` means `template[0]` aligns with `signal[l]`
+    /// (see [`Mode::lag_at`])
+    Lags(RangeInclusive<isize>),
+    /// Full-mode output indices `start..=end`; index `k` is lag `k - (template.len() - 1)`
+    FullIndices(RangeInclusive<usize>),
+}
+
+impl LagWindow {
+    /// First and last signed lag of the window for a template of `template_len` samples
+    pub fn lag_bounds(&self, template_len: usize) -> (isize, isize) {
+        match self {
+            LagWindow::Lags(lags) => (*lags.start(), *lags.end()),
+            LagWindow::FullIndices(indices) => (
+                Mode::Full.lag_at(*indices.start(), template_len),
+                Mode::Full.lag_at(*indices.end(), template_len),
+            ),
+        }
+    }
+}
+
+impl From<RangeInclusive<isize>> for LagWindow {
+    fn from(lags: RangeInclusive<isize>) -> Self {
+        LagWindow::Lags(lags)
+    }
+}
+
+/// Correlate `signal` and `template` at the lags in `window` only
+///
+/// Returns `(values, start_lag)`: `values[k]` is the correlation at lag `start_lag + k`, with
+/// the same values as the matching entries of `fft_correlate_1d(signal, template, Mode::Full)`
+/// up to round-off. The window is clipped to the lags where the inputs overlap,
+/// `-(template.len() - 1)..=signal.len() - 1`, so `start_lag` can be later than requested. If
+/// nothing overlaps, or either input is empty, `values` is empty.
+///
+/// Work is proportional to the window length and the template length, not to the signal length.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidLagRange` if the window is empty, and
+/// `FftCorrelationError::FftProcessing` if FFT processing fails. Windows wider than the overlap,
+/// up to `isize::MIN..=isize::MAX`, are fine: only the clipped lags are allocated.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{correlate_lag_window, LagWindow};
+///
+/// let mut signal = vec![0.0f64; 1_000_000];
+/// signal[500_123] = 1.0;
+/// let template = [1.0, 0.5];
+/// let (values, start_lag) = correlate_lag_window(&signal, &template, 500_000..=500_200).unwrap();
+/// assert_eq!((values.len(), start_lag), (201, 500_000));
+/// assert!((values[123] - 1.0).abs() < 1e-12);
+/// assert!((values[122] - 0.5).abs() < 1e-12);
+///
+/// // The same window in Full-mode indices, clipped at the end of the signal
+/// let (tail, start_lag) = correlate_lag_window(&signal, &template, LagWindow::FullIndices(999_990..=1_000_010)).unwrap();
+/// assert_eq!((tail.len(), start_lag), (11, 999_989));
+/// ```
+pub fn correlate_lag_window<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    window: impl Into<LagWindow>,
+) -> Result<(Vec<T>, isize)> {
+    let (start, end) = window.into().lag_bounds(template.len());
+    if start > end {
+        return Err(FftCorrelationError::InvalidLagRange { start, end });
+    }
+    let start_lag = start.max(1 - template.len() as isize);
+    let end_lag = end.min(signal.len() as isize - 1);
+    if signal.is_empty() || template.is_empty() || start_lag > end_lag {
+        return Ok((Vec::new(), start_lag));
+    }
+    let values = window_values(signal, template, start_lag, end_lag, Method::Auto, FftSizing::default())?;
+    Ok((values, start_lag))
+}
+
+// Number of lags in start..=end, or `InvalidLagRange` if the range is empty or its length does
+// not fit in `usize`
+pub(crate) fn lag_count(start: isize, end: isize) -> Result<usize> {
+    if start > end {
+        return Err(FftCorrelationError::InvalidLagRange { start, end });
+    }
+    end.abs_diff(start).checked_add(1).ok_or(FftCorrelationError::InvalidLagRange { start, end })
+}
+
+// Empty vector with room for one entry per lag in start..=end, or `InvalidLagRange` if the range
+// is empty or that many entries cannot be allocated
+pub(crate) fn lag_buffer<V>(start: isize, end: isize) -> Result<Vec<V>> {
+    let mut buffer = Vec::new();
+    buffer.try_reserve_exact(lag_count(start, end)?).map_err(|_| FftCorrelationError::InvalidLagRange { start, end })?;
+    Ok(buffer)
+}
+
+// Upper end of the lags -max_lag..=max_lag (or 0..=max_lag), or `InvalidLagRange` if `max_lag` is
+// not a valid lag
+pub(crate) fn max_lag_bound(max_lag: usize) -> Result<isize> {
+    isize::try_from(max_lag).map_err(|_| FftCorrelationError::InvalidLagRange { start: isize::MIN, end: isize::MAX })
+}
+
+// Correlation at lags start..=end (one value per lag, zero where the inputs do not overlap),
+// computed blockwise with `method`; empty if either input is empty
+//
+// Returns `InvalidLagRange` if the range is empty or its values cannot be allocated.
+pub(crate) fn window_values<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    start: isize,
+    end: isize,
+    method: Method,
+    sizing: FftSizing,
+) -> Result<Vec<T>> {
+    padded_window(signal.len(), template.len(), start, end, |first, last| {
+        block_values(signal, template, first, last, method, sizing)
+    })
+}
+
+// Like `window_values`, but masked-out samples are ignored and lags whose overlap includes one
+// are NaN (see `masked_correlate_1d`); only the signal samples under the window are transformed
+pub(crate) fn masked_window_values<T: FftFloat>(
+    signal: &[T],
+    signal_mask: &[bool],
+    template: &[T],
+    template_mask: &[bool],
+    start: isize,
+    end: isize,
+) -> Result<Vec<T>> {
+    padded_window(signal.len(), template.len(), start, end, |first, last| {
+        let len = last.abs_diff(first) + template.len();
+        let segment = signal_segment(signal, first, len, T::zero());
+        let segment_mask = signal_segment(signal_mask, first, len, true);
+        let clamp = |index: isize| index.clamp(0, len as isize) as usize;
+        let support = clamp(-first)..clamp(signal.len() as isize - first);
+        masked_segment_correlate_1d(&segment, &segment_mask, support, template, template_mask, Mode::Valid)
+    })
+}
+
+// One value per lag in start..=end: `overlapping(first, last)` supplies the lags that overlap the
+// signal and the rest are zero; empty if either length is zero
+fn padded_window<T: FftFloat>(
+    signal_len: usize,
+    template_len: usize,
+    start: isize,
+    end: isize,
+    overlapping: impl FnOnce(isize, isize) -> Result<Vec<T>>,
+) -> Result<Vec<T>> {
+    let count = lag_count(start, end)?;
+    if signal_len == 0 || template_len == 0 {
+        return Ok(Vec::new());
+    }
+    let mut values = lag_buffer(start, end)?;
+
+    let first = start.max(1 - template_len as isize);
+    let last = end.min(signal_len as isize - 1);
+    if first <= last {
+        values.resize(first.abs_diff(start), T::zero());
+        values.extend(overlapping(first, last)?);
+    }
+    values.resize(count, T::zero());
+    Ok(values)
+}
+
+// Correlation at lags first..=last, all of which overlap the signal, in overlap-save blocks
+fn block_values<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    first: isize,
+    last: isize,
+    method: Method,
+    sizing: FftSizing,
+) -> Result<Vec<T>> {
+    let template_len = template.len();
+    // Short windows fit in one block no longer than their own segment
+    let segment_len = last.abs_diff(first) + template_len;
+    let fft_size = sizing.fft_len((BLOCK_FFT_FACTOR * template_len).max(MIN_BLOCK_LAGS + template_len - 1).min(segment_len));
+    let block_lags = fft_size - (template_len - 1);
+    let use_fft = match method {
+        Method::Auto => choose_method(fft_size, template_len, Mode::Valid) != Method::Direct,
+        Method::Fft => true,
+        Method::Direct => false,
+    };
+    let plans = if use_fft {
+        let (r2c, c2r) = plan_fft::<T>(fft_size);
+        let template_spectrum = reversed_template_spectrum(template, fft_size, r2c.as_ref())?;
+        Some((r2c, c2r, template_spectrum))
+    } else {
+        None
+    };
+
+    let blocks: Vec<(isize, isize)> = (first..=last)
+        .step_by(block_lags)
+        .map(|block_start| (block_start, last.min(block_start + block_lags as isize - 1)))
+        .collect();
+    #[cfg(feature = "rayon")]
+    let block_iter = blocks.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let block_iter = blocks.iter();
+    let block_values = block_iter
+        .map(|&(block_start, block_end)| {
+            // Each segment is at most `fft_size` long; circular wrap-around only reaches the
+            // discarded non-Valid lags
+            let segment = signal_segment(signal, block_start, (block_end - block_start) as usize + template_len, T::zero());
+            match &plans {
+                Some((r2c, c2r, template_spectrum)) => correlate_with_template_spectrum(
+                    &segment,
+                    template_len,
+                    template_spectrum,
+                    fft_size,
+                    r2c.as_ref(),
+                    c2r.as_ref(),
+                    Mode::Valid,
+                ),
+                None => Ok(direct_correlate_1d(&segment, template, Mode::Valid)),
+            }
+        })
+        .collect::<Result<Vec<Vec<T>>>>()?;
+    Ok(block_values.into_iter().flatten().collect())
+}
+
+// `len` samples of the signal starting at `offset`, padded with `fill` where they fall outside it
+fn signal_segment<V: Clone>(signal: &[V], offset: isize, len: usize, fill: V) -> Cow<'_, [V]> {
+    if offset >= 0 && offset as usize + len <= signal.len() {
+        return Cow::Borrowed(&signal[offset as usize..offset as usize + len]);
+    }
+    Cow::Owned(
+        (0..len)
+            .map(|j| {
+                let index = offset + j as isize;
+                if index >= 0 && (index as usize) < signal.len() {
+                    signal[index as usize].clone()
+                } else {
+                    fill.clone()
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    fn test_signal(len: usize, seed: f64) -> Vec<f64> {
+        (0..len).map(|i| ((i as f64) * seed).sin() + 0.4 * ((i as f64) * seed * 2.7).cos()).collect()
+    }
+
+    fn assert_matches_full(signal: &[f64], template: &[f64], start: isize, end: isize) {
+        let full = fft_correlate_1d(signal, template, Mode::Full).unwrap();
+        let (values, start_lag) = correlate_lag_window(signal, template, start..=end).unwrap();
+        let clipped_start = start.max(1 - template.len() as isize);
+        let clipped_end = end.min(signal.len() as isize - 1);
+        assert_eq!(start_lag, clipped_start);
+        assert_eq!(values.len() as isize, (clipped_end - clipped_start + 1).max(0));
+        for (k, value) in values.iter().enumerate() {
+            let index = Mode::Full.index_of_lag(start_lag + k as isize, signal.len(), template.len()).unwrap();
+            assert!((value - full[index]).abs() < 1e-9, "{}..={} lag {}", start, end, start_lag + k as isize);
+        }
+    }
+
+    #[test]
+    fn test_window_matches_full_output() {
+        let signal = test_signal(200, 0.17);
+        for template_len in [1, 2, 7, 64, 250] {
+            let template = test_signal(template_len, 0.61);
+            for (start, end) in [(0, 0), (-3, 3), (-300, -250), (-10, 40), (150, 260), (-400, 400), (199, 199)] {
+                assert_matches_full(&signal, &template, start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_index_convention_and_clipping() {
+        let signal = test_signal(50, 0.3);
+        let template = test_signal(6, 0.8);
+        let by_index = correlate_lag_window(&signal, &template, LagWindow::FullIndices(0..=9)).unwrap();
+        let by_lag = correlate_lag_window(&signal, &template, -5..=4).unwrap();
+        assert_eq!(by_index, by_lag);
+        assert_eq!(by_index.1, -5);
+
+        let (values, start_lag) = correlate_lag_window(&signal, &template, 60..=70).unwrap();
+        assert!(values.is_empty());
+        assert_eq!(start_lag, 60);
+        let (start, end) = (2, 1);
+        assert!(matches!(
+            correlate_lag_window(&signal, &template, start..=end),
+            Err(FftCorrelationError::InvalidLagRange { start: 2, end: 1 })
+        ));
+        assert!(correlate_lag_window::<f64>(&[], &template, 0..=3).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_long_window_is_split_into_blocks() {
+        // 20_000 lags of a 3-tap template span several MIN_BLOCK_LAGS blocks
+        let signal = test_signal(30_000, 0.011);
+        let template = [0.25, -1.0, 0.5];
+        assert_matches_full(&signal, &template, -2, 19_997);
+        assert_matches_full(&signal, &template, 4_000, 29_999);
+    }
+
+    #[test]
+    fn test_fft_blocks_share_template_spectrum() {
+        // Several overlap-save blocks per window, both for a short and a long template
+        let signal = test_signal(30_000, 0.011);
+        for template_len in [3, 700] {
+            let template = test_signal(template_len, 0.23);
+            let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+            for sizing in [FftSizing::Fast, FftSizing::PowerOfTwo] {
+                let values = window_values(&signal, &template, -1_000, 29_000, Method::Fft, sizing).unwrap();
+                assert_eq!(values.len(), 30_001);
+                for (k, lag) in (-1_000..=29_000).enumerate() {
+                    let expected = Mode::Full.index_of_lag(lag, 30_000, template_len).map_or(0.0, |index| full[index]);
+                    assert!((values[k] - expected).abs() < 1e-9, "{} taps {:?} lag {}", template_len, sizing, lag);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_widest_windows_do_not_overflow() {
+        let signal = test_signal(40, 0.5);
+        let template = test_signal(5, 0.2);
+        let (values, start_lag) = correlate_lag_window(&signal, &template, isize::MIN..=isize::MAX).unwrap();
+        assert_eq!((values.len(), start_lag), (44, -4));
+        assert_matches_full(&signal, &template, isize::MIN, isize::MAX);
+
+        // Padding every requested lag is impossible for ranges this wide
+        for (start, end) in [(isize::MIN, isize::MAX), (isize::MIN / 2, isize::MAX / 2)] {
+            assert!(matches!(
+                window_values(&signal, &template, start, end, Method::Auto, FftSizing::Fast),
+                Err(FftCorrelationError::InvalidLagRange { .. })
+            ));
+        }
+        assert!(matches!(
+            crate::Correlator::<f64>::builder().lag_range(isize::MIN..=isize::MAX).build(),
+            Err(FftCorrelationError::InvalidLagRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_window_values_pad_and_methods_agree() {
+        let signal = test_signal(40, 0.5);
+        let template = test_signal(5, 0.2);
+        let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+        for method in [Method::Auto, Method::Fft, Method::Direct] {
+            let values = window_values(&signal, &template, -8, 45, method, FftSizing::PowerOfTwo).unwrap();
+            assert_eq!(values.len(), 54);
+            for (k, lag) in (-8..=45).enumerate() {
+                let expected = Mode::Full.index_of_lag(lag, 40, 5).map_or(0.0, |index| full[index]);
+                assert!((values[k] - expected).abs() < 1e-10, "{:?} lag {}", method, lag);
+            }
+        }
+    }
+}
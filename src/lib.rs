@@ -2,6 +2,7 @@
 //!
 //! Provides efficient cross-correlation using FFT with configurable output modes (Full, Same, Valid)
 //! matching scipy/numpy conventions. Uses thread-local FFT planner caching for optimal performance.
+//! All functions are generic over `f32` and `f64` via [`FftFloat`].
 //!
 //! # Mode Semantics and Indexing
 //!
@@ -22,16 +23,67 @@
 //! - scipy.signal.correlate: https://docs.scipy.org/doc/scipy/reference/generated/scipy.signal.correlate.html
 //! - numpy.correlate: https://numpy.org/doc/stable/reference/generated/numpy.correlate.html
 
-use realfft::RealFftPlanner;
-use std::cell::RefCell;
-
+use realfft::{ComplexToReal, RealToComplex};
+use rustfft::Fft;
+use std::sync::Arc;
+
+pub mod autocorr;
+pub mod batch;
+pub mod complex;
+pub mod convolve;
+pub mod correlate2d;
+pub mod correlate_nd;
+pub mod correlation;
+pub mod correlator;
+pub mod direct;
 pub mod error;
-pub use error::{FftCorrelationError, Result};
-
-// Thread-local FFT planner cache for optimal performance
-thread_local! {
-    static FFT_PLANNER: RefCell<RealFftPlanner<f32>> = RefCell::new(RealFftPlanner::new());
-}
+pub mod float;
+pub mod gcc;
+pub mod interpolate;
+pub mod lag_window;
+pub mod masked;
+pub mod matrix;
+pub mod multichannel;
+pub mod ncc;
+pub mod nonfinite;
+pub mod peak;
+pub mod sizing;
+pub mod streaming;
+pub mod template;
+pub mod validate;
+pub mod workspace;
+pub mod xcorr;
+pub use autocorr::{autocorrelate, AutocorrOptions, Scaling};
+pub use batch::{fft_correlate_batch, fft_correlate_batch_template};
+pub use complex::fft_correlate_1d_complex;
+pub use convolve::fft_convolve_1d;
+pub use correlate2d::fft_correlate_2d;
+#[cfg(feature = "ndarray")]
+pub use correlate_nd::fft_correlate_array;
+pub use correlate_nd::fft_correlate_nd;
+pub use correlation::{fft_correlate_1d_with_lags, Correlation};
+pub use correlator::{Correlator, CorrelatorBuilder};
+pub use direct::{choose_method, correlate_1d, direct_correlate_1d, Method};
+pub use error::{FftCorrelationError, InputKind, Result};
+pub use float::FftFloat;
+pub use gcc::{fft_gcc_1d, GccOptions, GccWeighting};
+pub use interpolate::{refine_peak, subsample_lag, Interpolation};
+pub use lag_window::{correlate_lag_window, LagWindow};
+pub use masked::{fft_masked_correlate_1d, MaskedNccOptions};
+pub use matrix::{fft_cross_correlation_matrix, CrossCorrelationMatrix};
+pub use multichannel::{fft_correlate_multichannel, fft_correlate_multichannel_sum, ChannelLayout};
+pub use ncc::{fft_normalized_correlate_1d, NccKind};
+pub use nonfinite::{fft_correlate_1d_with_policy, NonFinitePolicy};
+pub use peak::{find_max_peak, find_peaks, Peak, PeakOptions};
+pub use sizing::{next_fast_len, FftSizing};
+pub use streaming::StreamingCorrelator;
+pub use template::TemplateCorrelator;
+pub use validate::{fft_correlate_1d_strict, validate_inputs};
+pub use workspace::{fft_correlate_1d_into, CorrelationWorkspace};
+pub use xcorr::xcorr;
+
+/// Complex sample type used by [`fft_correlate_1d_complex`]
+pub use realfft::num_complex::Complex;
 
 /// Output mode for correlation, matching scipy/numpy conventions
 ///
@@ -64,10 +116,57 @@ pub enum Mode {
     Valid,
 }
 
+impl Mode {
+    /// Length of the correlation output for inputs of the given lengths
+    ///
+    /// Returns 0 if either input is empty, or in Valid mode if the template is longer
+    /// than the signal.
+    pub fn output_len(self, signal_len: usize, template_len: usize) -> usize {
+        if signal_len == 0 || template_len == 0 {
+            return 0;
+        }
+        match self {
+            Mode::Full => signal_len + template_len - 1,
+            Mode::Same => signal_len,
+            Mode::Valid => (signal_len + 1).saturating_sub(template_len),
+        }
+    }
+
+    /// Index in the Full output corresponding to index 0 of this mode's output
+    pub fn full_offset(self, template_len: usize) -> usize {
+        let overhang = template_len.saturating_sub(1);
+        match self {
+            Mode::Full => 0,
+            Mode::Same => overhang / 2,
+            Mode::Valid => overhang,
+        }
+    }
+
+    /// Signed lag of output index `index`
+    ///
+    /// Lag `l` means `template[0]` aligns with `signal[l]`, so the output value is
+    /// `sum_i signal[l + i] * template[i]`. Full index `k` therefore has lag
+    /// `k - (template_len - 1)`, and Valid index `k` has lag `k`. This matches
+    /// `scipy.signal.correlation_lags` for Full and Valid modes.
+    pub fn lag_at(self, index: usize, template_len: usize) -> isize {
+        (index + self.full_offset(template_len)) as isize - template_len.saturating_sub(1) as isize
+    }
+
+    /// Output index holding lag `lag`, or `None` if that lag is outside this mode's output
+    pub fn index_of_lag(self, lag: isize, signal_len: usize, template_len: usize) -> Option<usize> {
+        // Lag of index 0 is -shift; shift never exceeds template_len - 1
+        let shift = isize::try_from(template_len.saturating_sub(1) - self.full_offset(template_len)).ok()?;
+        let index = usize::try_from(lag.checked_add(shift)?).ok()?;
+        (index < self.output_len(signal_len, template_len)).then_some(index)
+    }
+}
+
 
 /// Correlate two 1D signals using FFT
 ///
 /// Computes cross-correlation efficiently using FFT with O(N log N) complexity.
+/// Works with either `f32` or `f64` samples; use `f64` for long recordings where
+/// single-precision round-off becomes noticeable.
 /// The `mode` parameter controls output size:
 /// - `Mode::Full`: Returns complete correlation (signal.len() + template.len() - 1)
 /// - `Mode::Same`: Returns centered output matching signal.len()
@@ -83,6 +182,9 @@ pub enum Mode {
 /// Returns an empty vector if either input is empty or if Valid mode is used
 /// with signal shorter than template.
 ///
+/// This is the default [`Correlator`] configuration with the given mode; use
+/// [`Correlator::builder`] for other methods, sizing, normalization or non-finite policies.
+///
 /// # Errors
 ///
 /// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
@@ -91,19 +193,76 @@ pub enum Mode {
 ///
 /// - scipy.signal.correlate: https://docs.scipy.org/doc/scipy/reference/generated/scipy.signal.correlate.html
 /// - numpy.correlate: https://numpy.org/doc/stable/reference/generated/numpy.correlate.html
-pub fn fft_correlate_1d(signal: &[f32], template: &[f32], mode: Mode) -> Result<Vec<f32>> {
+pub fn fft_correlate_1d<T: FftFloat>(signal: &[T], template: &[T], mode: Mode) -> Result<Vec<T>> {
+    Correlator::builder().mode(mode).build()?.correlate(signal, template)
+}
+
+/// Correlate two 1D signals using FFT with an explicit FFT length policy
+///
+/// Identical to [`fft_correlate_1d`] except that the padded FFT length is chosen by `sizing`
+/// instead of the default [`FftSizing::Fast`]. Results agree up to floating-point round-off.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+pub fn fft_correlate_1d_with_sizing<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    mode: Mode,
+    sizing: FftSizing,
+) -> Result<Vec<T>> {
     // Early validation for empty inputs
     if signal.is_empty() || template.is_empty() {
         return Ok(Vec::new());
     }
 
     let output_len = signal.len() + template.len() - 1;
-    let fft_size = output_len.next_power_of_two();
+    let fft_size = sizing.fft_len(output_len);
 
-    // Zero-pad both signal and template to fft_size
-    let mut padded_signal = vec![0.0; fft_size];
-    let mut padded_template = vec![0.0; fft_size];
-    padded_signal[..signal.len()].copy_from_slice(signal);
+    // Get plans from thread-local cached planner
+    // RealFftPlanner caches FFT plans internally, so reusing it avoids repeated planning
+    let (r2c, c2r) = plan_fft(fft_size);
+
+    let template_spectrum = reversed_template_spectrum(template, fft_size, r2c.as_ref())?;
+    correlate_with_template_spectrum(
+        signal,
+        template.len(),
+        &template_spectrum,
+        fft_size,
+        r2c.as_ref(),
+        c2r.as_ref(),
+        mode,
+    )
+}
+
+/// Fetch forward and inverse real FFT plans of `fft_size` from the thread-local planner
+pub(crate) fn plan_fft<T: FftFloat>(fft_size: usize) -> (Arc<dyn RealToComplex<T>>, Arc<dyn ComplexToReal<T>>) {
+    T::with_planner(|planner| {
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+        (r2c, c2r)
+    })
+}
+
+/// Fetch forward and inverse complex FFT plans of `fft_size` from the thread-local planner
+pub(crate) fn plan_complex_fft<T: FftFloat>(fft_size: usize) -> (Arc<dyn Fft<T>>, Arc<dyn Fft<T>>) {
+    T::with_complex_planner(|planner| {
+        let forward = planner.plan_fft_forward(fft_size);
+        let inverse = planner.plan_fft_inverse(fft_size);
+        (forward, inverse)
+    })
+}
+
+/// Compute the spectrum of the time-reversed, zero-padded template
+///
+/// The result can be multiplied directly with a signal spectrum of the same `fft_size`
+/// to obtain the correlation.
+pub(crate) fn reversed_template_spectrum<T: FftFloat>(
+    template: &[T],
+    fft_size: usize,
+    r2c: &dyn RealToComplex<T>,
+) -> Result<Vec<Complex<T>>> {
+    let mut padded_template = vec![T::zero(); fft_size];
 
     // Reverse template for correlation via the Correlation Theorem:
     // For real-valued signals, correlation(x, y) = IFFT(FFT(x) * conj(FFT(y)))
@@ -115,76 +274,110 @@ pub fn fft_correlate_1d(signal: &[f32], template: &[f32], mode: Mode) -> Result<
         padded_template[i] = val;
     }
 
-    // Get plans from thread-local cached planner
-    // RealFftPlanner caches FFT plans internally, so reusing it avoids repeated planning
-    let (r2c, c2r) = FFT_PLANNER.with(|planner_cell| {
-        let mut planner = planner_cell.borrow_mut();
-        let r2c = planner.plan_fft_forward(fft_size);
-        let c2r = planner.plan_fft_inverse(fft_size);
-        (r2c, c2r)
-    });
-
-    // Allocate buffers for FFT output (complex)
-    let mut signal_spectrum = r2c.make_output_vec();
     let mut template_spectrum = r2c.make_output_vec();
-
-    // Forward FFT on both signal and template
-    debug_assert_eq!(padded_signal.len(), fft_size, "Signal buffer size mismatch");
     debug_assert_eq!(padded_template.len(), fft_size, "Template buffer size mismatch");
-    r2c.process(&mut padded_signal, &mut signal_spectrum)
-        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for signal: {:?}", e)))?;
     r2c.process(&mut padded_template, &mut template_spectrum)
         .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for template: {:?}", e)))?;
+    Ok(template_spectrum)
+}
+
+/// Correlate a signal against a precomputed reversed-template spectrum
+///
+/// `template_spectrum` must come from [`reversed_template_spectrum`] with the same `fft_size`,
+/// and `fft_size` must be at least `signal.len() + template_len - 1`. In Valid mode
+/// `signal.len()` suffices (overlap-save): circular wrap-around then only reaches the discarded
+/// lags.
+pub(crate) fn correlate_with_template_spectrum<T: FftFloat>(
+    signal: &[T],
+    template_len: usize,
+    template_spectrum: &[Complex<T>],
+    fft_size: usize,
+    r2c: &dyn RealToComplex<T>,
+    c2r: &dyn ComplexToReal<T>,
+    mode: Mode,
+) -> Result<Vec<T>> {
+    let mut signal_spectrum = padded_spectrum(signal, fft_size, r2c)?;
+    debug_assert_eq!(template_spectrum.len(), signal_spectrum.len(), "Template spectrum size mismatch");
 
     // Frequency domain multiplication (element-wise)
     // For correlation, we already reversed template, so just multiply in-place
-    for i in 0..signal_spectrum.len() {
-        signal_spectrum[i] *= template_spectrum[i];
+    for (s, t) in signal_spectrum.iter_mut().zip(template_spectrum.iter()) {
+        *s *= t;
     }
 
+    inverse_to_output(signal_spectrum, fft_size, c2r, signal.len(), template_len, mode)
+}
+
+/// Zero-pad `signal` to `fft_size` and compute its forward spectrum
+pub(crate) fn padded_spectrum<T: FftFloat>(
+    signal: &[T],
+    fft_size: usize,
+    r2c: &dyn RealToComplex<T>,
+) -> Result<Vec<Complex<T>>> {
+    // Zero-pad signal to fft_size
+    let mut padded_signal = vec![T::zero(); fft_size];
+    padded_signal[..signal.len()].copy_from_slice(signal);
+
+    // Allocate buffer for FFT output (complex)
+    let mut signal_spectrum = r2c.make_output_vec();
+
+    // Forward FFT on signal
+    debug_assert_eq!(padded_signal.len(), fft_size, "Signal buffer size mismatch");
+    r2c.process(&mut padded_signal, &mut signal_spectrum)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for signal: {:?}", e)))?;
+    Ok(signal_spectrum)
+}
+
+/// Inverse-transform a product spectrum, normalize, and trim it to `mode`
+pub(crate) fn inverse_to_output<T: FftFloat>(
+    mut spectrum: Vec<Complex<T>>,
+    fft_size: usize,
+    c2r: &dyn ComplexToReal<T>,
+    signal_len: usize,
+    template_len: usize,
+    mode: Mode,
+) -> Result<Vec<T>> {
     // Inverse FFT
-    let mut result_time = vec![0.0; fft_size];
-    debug_assert_eq!(signal_spectrum.len(), r2c.make_output_vec().len(), "Spectrum buffer size mismatch");
+    let mut result_time = vec![T::zero(); fft_size];
     debug_assert_eq!(result_time.len(), fft_size, "Output buffer size mismatch");
-    c2r.process(&mut signal_spectrum, &mut result_time)
+    c2r.process(&mut spectrum, &mut result_time)
         .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
 
     // Normalize by FFT size
-    let normalization = fft_size as f32;
+    let normalization = T::from_len(fft_size);
     result_time.iter_mut().for_each(|x| *x /= normalization);
 
-    // Mode-based output trimming
+    Ok(trim_full_output(result_time, signal_len, template_len, mode))
+}
+
+/// Trim a buffer whose prefix holds the Full correlation to the requested `mode`
+pub(crate) fn trim_full_output<T: Copy>(mut full: Vec<T>, signal_len: usize, template_len: usize, mode: Mode) -> Vec<T> {
+    let len = mode.output_len(signal_len, template_len);
     match mode {
         Mode::Full => {
-            result_time.truncate(output_len);
-            Ok(result_time)
-        }
-        Mode::Same => {
-            let start = (output_len - signal.len()) / 2;
-            Ok(result_time[start..start + signal.len()].to_vec())
+            full.truncate(len);
+            full
         }
-        Mode::Valid => {
-            if signal.len() < template.len() {
-                return Ok(Vec::new());
-            }
-            let valid_len = signal.len() - template.len() + 1;
-            let start = template.len() - 1;
-            Ok(result_time[start..start + valid_len].to_vec())
+        Mode::Same | Mode::Valid => {
+            let start = mode.full_offset(template_len);
+            full[start..start + len].to_vec()
         }
     }
 }
 
 #[cfg(test)]
+// Index loops in the long-standing tests are kept as written
+#[allow(clippy::needless_range_loop, clippy::manual_range_contains)]
 mod tests {
     use super::*;
 
     // Naive correlation used for correctness checks in tests.
-    fn naive_full_correlation(signal: &[f32], template: &[f32]) -> Vec<f32> {
+    fn naive_full_correlation<T: FftFloat>(signal: &[T], template: &[T]) -> Vec<T> {
         let output_len = signal.len() + template.len() - 1;
-        let mut result = vec![0.0; output_len];
+        let mut result = vec![T::zero(); output_len];
 
         for lag in 0..output_len {
-            let mut correlation = 0.0;
+            let mut correlation = T::zero();
             for i in 0..template.len() {
                 let signal_idx = lag as isize - (template.len() as isize - 1) + i as isize;
                 if (0..signal.len() as isize).contains(&signal_idx) {
@@ -199,53 +392,53 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_mode_full_length() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let template = vec![1.0, 0.0, 0.0];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let template: Vec<f32> = vec![1.0, 0.0, 0.0];
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         assert_eq!(result.len(), signal.len() + template.len() - 1);
 
-        let signal = vec![1.0; 100];
-        let template = vec![1.0; 10];
+        let signal: Vec<f32> = vec![1.0; 100];
+        let template: Vec<f32> = vec![1.0; 10];
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         assert_eq!(result.len(), 109);
     }
 
     #[test]
     fn test_fft_correlate_mode_same_length() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let template = vec![1.0, 0.0, 0.0];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let template: Vec<f32> = vec![1.0, 0.0, 0.0];
         let result = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
         assert_eq!(result.len(), signal.len());
 
-        let signal = vec![1.0; 100];
-        let template = vec![1.0; 10];
+        let signal: Vec<f32> = vec![1.0; 100];
+        let template: Vec<f32> = vec![1.0; 10];
         let result = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
         assert_eq!(result.len(), 100);
     }
 
     #[test]
     fn test_fft_correlate_mode_valid_length() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let template = vec![1.0, 0.0, 0.0];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let template: Vec<f32> = vec![1.0, 0.0, 0.0];
         let result = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
         assert_eq!(result.len(), signal.len() - template.len() + 1);
 
-        let signal = vec![1.0; 100];
-        let template = vec![1.0; 10];
+        let signal: Vec<f32> = vec![1.0; 100];
+        let template: Vec<f32> = vec![1.0; 10];
         let result = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
         assert_eq!(result.len(), 91);
 
         // Template longer than signal
-        let signal = vec![1.0, 2.0];
-        let template = vec![1.0; 10];
+        let signal: Vec<f32> = vec![1.0, 2.0];
+        let template: Vec<f32> = vec![1.0; 10];
         let result = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
         assert_eq!(result.len(), 0);
     }
 
     #[test]
     fn test_fft_correlate_mode_full_impulse() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let template = vec![1.0, 0.0, 0.0];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let template: Vec<f32> = vec![1.0, 0.0, 0.0];
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
 
         // Correlation with impulse at position 0 should return signal shifted
@@ -259,8 +452,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_mode_same_centering() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let template = vec![1.0, 0.0, 0.0];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let template: Vec<f32> = vec![1.0, 0.0, 0.0];
 
         let full_result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same_result = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
@@ -278,8 +471,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_mode_valid_no_edges() {
-        let signal = vec![0.0, 0.0, 1.0, 2.0, 3.0, 0.0, 0.0];
-        let template = vec![1.0, 1.0, 1.0];
+        let signal: Vec<f32> = vec![0.0, 0.0, 1.0, 2.0, 3.0, 0.0, 0.0];
+        let template: Vec<f32> = vec![1.0, 1.0, 1.0];
 
         let valid_result = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
 
@@ -298,8 +491,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_modes_consistency() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0];
-        let template = vec![0.5, 1.0, 0.5];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0];
+        let template: Vec<f32> = vec![0.5, 1.0, 0.5];
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
@@ -321,8 +514,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_vs_sliding_window() {
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0];
-        let template = vec![0.5, 1.0, 0.5];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0];
+        let template: Vec<f32> = vec![0.5, 1.0, 0.5];
 
         let fft_result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let sliding_result = naive_full_correlation(&signal, &template);
@@ -334,6 +527,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fft_correlate_vs_sliding_window_f64() {
+        let signal: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0];
+        let template: Vec<f64> = vec![0.5, 1.0, 0.5];
+
+        let fft_result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+        let sliding_result = naive_full_correlation(&signal, &template);
+
+        assert_eq!(fft_result.len(), sliding_result.len());
+        for (i, (&fft_val, &sliding_val)) in fft_result.iter().zip(sliding_result.iter()).enumerate() {
+            assert!((fft_val - sliding_val).abs() < 1e-12,
+                "Sample {} mismatch: FFT={}, sliding={}", i, fft_val, sliding_val);
+        }
+    }
+
     #[test]
     fn test_fft_correlate_chirp_signals() {
         use std::f32::consts::PI;
@@ -374,20 +582,20 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_empty_inputs() {
-        let result1 = fft_correlate_1d(&[], &[1.0, 2.0, 3.0], Mode::Full).unwrap();
+        let result1 = fft_correlate_1d::<f32>(&[], &[1.0, 2.0, 3.0], Mode::Full).unwrap();
         assert_eq!(result1.len(), 0);
 
-        let result2 = fft_correlate_1d(&[1.0, 2.0, 3.0], &[], Mode::Full).unwrap();
+        let result2 = fft_correlate_1d::<f32>(&[1.0, 2.0, 3.0], &[], Mode::Full).unwrap();
         assert_eq!(result2.len(), 0);
 
-        let result3 = fft_correlate_1d(&[], &[], Mode::Full).unwrap();
+        let result3 = fft_correlate_1d::<f32>(&[], &[], Mode::Full).unwrap();
         assert_eq!(result3.len(), 0);
     }
 
     #[test]
     fn test_fft_correlate_single_element() {
-        let signal = vec![5.0];
-        let template = vec![2.0];
+        let signal: Vec<f32> = vec![5.0];
+        let template: Vec<f32> = vec![2.0];
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         assert_eq!(full.len(), 1);
@@ -406,8 +614,8 @@ mod tests {
     fn test_fft_correlate_time_reversal_equivalence() {
         // Verify that correlation is equivalent to time-reversing template
         // for real-valued signals: correlate(x, y) ≡ convolve(x, reverse(y))
-        let signal = vec![1.0, 2.0, 3.0, 4.0];
-        let template = vec![0.5, 1.0, 1.5];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+        let template: Vec<f32> = vec![0.5, 1.0, 1.5];
 
         // Compute correlation in both directions
         let result_xy = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
@@ -429,8 +637,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_equal_length() {
-        let signal = vec![1.0, 2.0, 3.0];
-        let template = vec![0.5, 1.0, 1.5];
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0];
+        let template: Vec<f32> = vec![0.5, 1.0, 1.5];
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         assert_eq!(full.len(), 5);
@@ -444,8 +652,8 @@ mod tests {
 
     #[test]
     fn test_fft_correlate_template_longer() {
-        let signal = vec![1.0, 2.0];
-        let template = vec![1.0; 10];
+        let signal: Vec<f32> = vec![1.0, 2.0];
+        let template: Vec<f32> = vec![1.0; 10];
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         assert_eq!(full.len(), 11);
@@ -460,7 +668,7 @@ mod tests {
     #[test]
     fn test_fft_correlate_normalization() {
         // Autocorrelation test
-        let signal = vec![1.0; 50];
+        let signal: Vec<f32> = vec![1.0; 50];
         let template = signal.clone();
 
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
@@ -479,8 +687,8 @@ mod tests {
         // Comment 3: Test even/odd length combinations for correct Same/Valid centering
 
         // Case 1: signal 8 (even), template 4 (even)
-        let signal = vec![1.0; 8];
-        let template = vec![0.5; 4];
+        let signal: Vec<f32> = vec![1.0; 8];
+        let template: Vec<f32> = vec![0.5; 4];
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
         let valid = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
@@ -503,8 +711,8 @@ mod tests {
         }
 
         // Case 2: signal 8 (even), template 3 (odd)
-        let signal = vec![1.0; 8];
-        let template = vec![0.5; 3];
+        let signal: Vec<f32> = vec![1.0; 8];
+        let template: Vec<f32> = vec![0.5; 3];
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
         let valid = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
@@ -514,8 +722,8 @@ mod tests {
         assert_eq!(valid.len(), 6);
 
         // Case 3: signal 7 (odd), template 4 (even)
-        let signal = vec![1.0; 7];
-        let template = vec![0.5; 4];
+        let signal: Vec<f32> = vec![1.0; 7];
+        let template: Vec<f32> = vec![0.5; 4];
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
         let valid = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
@@ -528,7 +736,7 @@ mod tests {
     #[test]
     fn test_fft_correlate_large_randomized_fft_sizing() {
         // Comment 4: Test FFT sizing with lengths straddling powers-of-two
-        // 1025 requires FFT size 2048, 513 requires FFT size 1024
+        // 1025 + 64 - 1 = 1088 pads to FFT size 1120 under the default FftSizing::Fast (2048 with PowerOfTwo)
 
         use std::f32::consts::PI;
 
@@ -564,8 +772,8 @@ mod tests {
     fn test_fft_correlate_all_zero_inputs() {
         // Comment 5: Test behavior with all-zero inputs
 
-        let signal = vec![0.0; 10];
-        let template = vec![0.0; 5];
+        let signal: Vec<f32> = vec![0.0; 10];
+        let template: Vec<f32> = vec![0.0; 5];
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
@@ -589,9 +797,9 @@ mod tests {
         // Note: FFT operations may fail with NaN/Inf; we document this behavior
 
         // Test signal with one very large value (avoiding NaN/Inf which break FFT)
-        let mut signal = vec![1.0; 10];
+        let mut signal: Vec<f32> = vec![1.0; 10];
         signal[5] = 1e10;  // Use large value instead of NaN (NaN breaks FFT validation)
-        let template = vec![0.5; 3];
+        let template: Vec<f32> = vec![0.5; 3];
 
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
 
@@ -599,9 +807,9 @@ mod tests {
         assert!(result.iter().any(|x| *x > 1e8), "Large values should be present in correlation");
 
         // Test with negative values
-        let mut signal = vec![1.0; 10];
+        let mut signal: Vec<f32> = vec![1.0; 10];
         signal[3] = -10.0;
-        let template = vec![0.5; 3];
+        let template: Vec<f32> = vec![0.5; 3];
 
         let result = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
 
@@ -614,8 +822,8 @@ mod tests {
         // Comment 6: Test Mode::Same centering when template is even (ambiguous center)
         // Verify left-biased centering for consistent alignment
 
-        let signal = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];  // length 6
-        let template = vec![0.5, 1.0, 1.5, 2.0];  // length 4 (even)
+        let signal: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];  // length 6
+        let template: Vec<f32> = vec![0.5, 1.0, 1.5, 2.0];  // length 4 (even)
 
         let full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
         let same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
@@ -740,6 +948,85 @@ mod tests {
         }
     }
 
+    // Compare all three modes against the naive correlation at precision T.
+    fn assert_matches_naive_across_modes<T: FftFloat + std::fmt::Display>(max_signal_len: usize, max_template_len: usize, tolerance: T) {
+        let c = |v: f64| T::from(v).unwrap();
+        for signal_len in 1..=max_signal_len {
+            for template_len in 1..=max_template_len {
+                let signal: Vec<T> = (0..signal_len)
+                    .map(|i| (T::from_len(i) * c(0.7)).cos() + T::from_len(i) * c(0.05))
+                    .collect();
+                let template: Vec<T> = (0..template_len)
+                    .map(|i| (T::from_len(i) * c(0.4)).sin() - T::from_len(i) * c(0.03))
+                    .collect();
+
+                let naive_full = naive_full_correlation(&signal, &template);
+                let fft_full = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+
+                assert_eq!(fft_full.len(), naive_full.len(), "Full length mismatch for signal {} template {}", signal_len, template_len);
+                for (idx, (expected, actual)) in naive_full.iter().zip(fft_full.iter()).enumerate() {
+                    assert!(
+                        (*expected - *actual).abs() < tolerance,
+                        "Full mode mismatch at {} for signal {} template {}: expected {}, got {}",
+                        idx,
+                        signal_len,
+                        template_len,
+                        expected,
+                        actual
+                    );
+                }
+
+                let fft_same = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
+                let same_start = (naive_full.len() - signal.len()) / 2;
+                let expected_same = &naive_full[same_start..same_start + signal.len()];
+                assert_eq!(fft_same.len(), expected_same.len(), "Same length mismatch for signal {} template {}", signal_len, template_len);
+                for (idx, (expected, actual)) in expected_same.iter().zip(fft_same.iter()).enumerate() {
+                    assert!(
+                        (*expected - *actual).abs() < tolerance,
+                        "Same mode mismatch at {} for signal {} template {}: expected {}, got {}",
+                        idx,
+                        signal_len,
+                        template_len,
+                        expected,
+                        actual
+                    );
+                }
+
+                let fft_valid = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+                if signal.len() < template.len() {
+                    assert!(fft_valid.is_empty(), "Valid mode should be empty for signal {} template {}", signal_len, template_len);
+                } else {
+                    let valid_start = template.len() - 1;
+                    let valid_len = signal.len() - template.len() + 1;
+                    let expected_valid = &naive_full[valid_start..valid_start + valid_len];
+                    assert_eq!(fft_valid.len(), expected_valid.len(), "Valid length mismatch for signal {} template {}", signal_len, template_len);
+                    for (idx, (expected, actual)) in expected_valid.iter().zip(fft_valid.iter()).enumerate() {
+                        assert!(
+                            (*expected - *actual).abs() < tolerance,
+                            "Valid mode mismatch at {} for signal {} template {}: expected {}, got {}",
+                            idx,
+                            signal_len,
+                            template_len,
+                            expected,
+                            actual
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_correlate_matches_naive_across_modes_small_vectors_f64() {
+        assert_matches_naive_across_modes::<f64>(5, 6, 1e-12);
+    }
+
+    #[test]
+    fn test_fft_correlate_matches_naive_across_modes_medium_vectors_f64() {
+        // Lengths straddle several FFT sizes; f64 keeps errors far below f32 round-off
+        assert_matches_naive_across_modes::<f64>(40, 17, 1e-10);
+    }
+
     #[test]
     fn test_fft_correlate_thread_safety_consistency() {
         use std::thread;
@@ -769,4 +1056,46 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_mode_lag_mapping_matches_naive() {
+        // Lag l must equal sum_i signal[l + i] * template[i] for every mode and index
+        let signal: Vec<f64> = vec![1.0, -2.0, 0.5, 3.0, 1.5, -1.0, 2.5];
+        for template_len in 1..=9 {
+            let template: Vec<f64> = (0..template_len).map(|i| 1.0 + i as f64 * 0.25).collect();
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                let output = fft_correlate_1d(&signal, &template, mode).unwrap();
+                assert_eq!(output.len(), mode.output_len(signal.len(), template_len));
+                for (index, value) in output.iter().enumerate() {
+                    let lag = mode.lag_at(index, template_len);
+                    let expected: f64 = (0..template_len)
+                        .filter_map(|i| {
+                            let idx = lag + i as isize;
+                            (0..signal.len() as isize).contains(&idx).then(|| signal[idx as usize] * template[i])
+                        })
+                        .sum();
+                    assert!((value - expected).abs() < 1e-9, "{:?} index {} lag {}", mode, index, lag);
+                    assert_eq!(mode.index_of_lag(lag, signal.len(), template_len), Some(index));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mode_index_of_lag_out_of_range() {
+        assert_eq!(Mode::Full.index_of_lag(-3, 5, 3), None);
+        assert_eq!(Mode::Full.index_of_lag(-2, 5, 3), Some(0));
+        assert_eq!(Mode::Full.index_of_lag(4, 5, 3), Some(6));
+        assert_eq!(Mode::Full.index_of_lag(5, 5, 3), None);
+        assert_eq!(Mode::Valid.index_of_lag(-1, 5, 3), None);
+        assert_eq!(Mode::Valid.index_of_lag(3, 5, 3), None);
+        assert_eq!(Mode::Same.lag_at(0, 3), -1);
+        assert_eq!(Mode::Valid.output_len(2, 10), 0);
+        assert_eq!(Mode::Same.output_len(0, 10), 0);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            for lag in [isize::MIN, isize::MIN + 1, isize::MAX - 1, isize::MAX] {
+                assert_eq!(mode.index_of_lag(lag, 5, 3), None, "{:?} lag {}", mode, lag);
+            }
+        }
+    }
 }
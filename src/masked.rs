@@ -0,0 +1,406 @@
+//! Masked normalized cross-correlation for data with gaps
+//!
+//! Filling missing samples with zeros biases ordinary correlation: windows that straddle a gap
+//! look less similar only because part of them is missing. [`fft_masked_correlate_1d`] takes a
+//! validity mask for the signal and the template and normalizes every lag over the samples that
+//! are valid in both, following Padfield's masked FFT registration.
+//!
+//! Every per-lag sum (overlap count, sums, sums of squares and cross products over the overlap) is
+//! one correlation of masked arrays, so the whole computation is six forward and six inverse FFTs.
+//! Sums are accumulated in `f64` because the Pearson variances subtract nearly equal terms.
+//!
+//! # References
+//!
+//! - D. Padfield, "Masked Object Registration in the Fourier Domain", IEEE Trans. Image
+//!   Processing 21(5), 2012
+
+use std::ops::Range;
+
+use crate::correlate2d::zero_real_bins;
+use crate::{padded_spectrum, plan_fft, FftCorrelationError, FftFloat, FftSizing, Mode, NccKind, Result};
+
+/// Options for [`fft_masked_correlate_1d`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskedNccOptions {
+    /// Normalization applied over the valid overlap of each lag
+    pub kind: NccKind,
+    /// Lags where fewer samples are valid in both inputs are reported as `0`
+    ///
+    /// Lags with a handful of overlapping samples give noisy values close to ±1; raising this
+    /// suppresses them.
+    pub min_overlap: usize,
+}
+
+impl MaskedNccOptions {
+    /// Options for `kind` that accept any non-empty overlap
+    pub fn new(kind: NccKind) -> Self {
+        Self { kind, min_overlap: 1 }
+    }
+
+    /// Set the minimum number of jointly valid samples per lag
+    pub fn with_min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap;
+        self
+    }
+}
+
+/// Normalized cross-correlation that ignores masked-out samples
+///
+/// `signal_mask[i]` and `template_mask[i]` mark valid samples; invalid ones are ignored entirely,
+/// so they may hold any value, including NaN. At each lag the selected [`NccKind`] is evaluated
+/// over the samples valid in both inputs. With all-true masks the result equals
+/// [`fft_normalized_correlate_1d`](crate::fft_normalized_correlate_1d) in Valid mode.
+///
+/// Output length and indexing follow [`Mode`] exactly as in
+/// [`fft_correlate_1d`](crate::fft_correlate_1d). Values lie in `[-1, 1]`; lags with fewer than
+/// `min_overlap` jointly valid samples, or whose overlap has (numerically) zero energy or variance,
+/// are `0`.
+///
+/// Returns an empty vector if either input is empty or if Valid mode is used
+/// with signal shorter than template.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::MaskLengthMismatch` if a mask differs in length from its data,
+/// and `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_masked_correlate_1d, MaskedNccOptions, Mode, NccKind};
+///
+/// let template = [1.0f64, 3.0, 2.0, 5.0];
+/// // The template appears at index 2, but one of its samples was lost
+/// let signal = [0.0f64, 4.0, 1.0, 3.0, f64::NAN, 5.0, 1.0, 0.0];
+/// let signal_mask: Vec<bool> = signal.iter().map(|v| !v.is_nan()).collect();
+///
+/// let options = MaskedNccOptions::new(NccKind::Pearson).with_min_overlap(3);
+/// let ncc = fft_masked_correlate_1d(&signal, &signal_mask, &template, &[true; 4], Mode::Valid, &options).unwrap();
+/// assert!((ncc[2] - 1.0).abs() < 1e-12);
+/// ```
+pub fn fft_masked_correlate_1d<T: FftFloat>(
+    signal: &[T],
+    signal_mask: &[bool],
+    template: &[T],
+    template_mask: &[bool],
+    mode: Mode,
+    options: &MaskedNccOptions,
+) -> Result<Vec<T>> {
+    check_mask(signal.len(), signal_mask.len())?;
+    check_mask(template.len(), template_mask.len())?;
+    let len = mode.output_len(signal.len(), template.len());
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let fft_size = FftSizing::default().fft_len(signal.len() + template.len() - 1);
+    let (r2c, c2r) = plan_fft::<f64>(fft_size);
+
+    // Masked powers 0, 1 and 2 of each input: mask, values and squares
+    let powers = |data: &[T], mask: &[bool]| -> Result<Vec<Vec<_>>> {
+        (0..3)
+            .map(|power| {
+                let masked: Vec<f64> = data
+                    .iter()
+                    .zip(mask)
+                    .map(|(&v, &valid)| if valid { to_f64(v).powi(power) } else { 0.0 })
+                    .collect();
+                padded_spectrum(&masked, fft_size, r2c.as_ref())
+            })
+            .collect()
+    };
+    let signal_spectra = powers(signal, signal_mask)?;
+    let template_spectra = powers(template, template_mask)?;
+
+    // Circular correlation of one signal power against one template power
+    let correlate = |s: usize, t: usize| -> Result<Vec<f64>> {
+        let mut cross: Vec<_> = signal_spectra[s].iter().zip(&template_spectra[t]).map(|(x, y)| x * y.conj()).collect();
+        zero_real_bins(&mut cross, fft_size);
+        let mut circular = c2r.make_output_vec();
+        c2r.process(&mut cross, &mut circular)
+            .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+        let normalization = fft_size as f64;
+        circular.iter_mut().for_each(|v| *v /= normalization);
+        Ok(circular)
+    };
+    let overlap = correlate(0, 0)?;
+    let sum_f = correlate(1, 0)?;
+    let sum_g = correlate(0, 1)?;
+    let sum_fg = correlate(1, 1)?;
+    let sum_ff = correlate(2, 0)?;
+    let sum_gg = correlate(0, 2)?;
+
+    // Energies below this fraction of the total are FFT round-off rather than signal
+    let tolerance = 1e3 * f64::EPSILON;
+    let energy = |data: &[T], mask: &[bool]| -> f64 {
+        data.iter().zip(mask).filter(|&(_, &valid)| valid).map(|(&v, _)| to_f64(v).powi(2)).sum()
+    };
+    let signal_floor = tolerance * energy(signal, signal_mask);
+    let template_floor = tolerance * energy(template, template_mask);
+
+    let min_overlap = options.min_overlap.max(1) as f64;
+    Ok((0..len)
+        .map(|index| {
+            let k = mode.lag_at(index, template.len()).rem_euclid(fft_size as isize) as usize;
+            let count = overlap[k].round();
+            if count < min_overlap {
+                return T::zero();
+            }
+            let (numerator, var_f, var_g) = match options.kind {
+                NccKind::Cosine => (sum_fg[k], sum_ff[k], sum_gg[k]),
+                NccKind::Pearson => (
+                    sum_fg[k] - sum_f[k] * sum_g[k] / count,
+                    sum_ff[k] - sum_f[k] * sum_f[k] / count,
+                    sum_gg[k] - sum_g[k] * sum_g[k] / count,
+                ),
+            };
+            if var_f <= signal_floor || var_g <= template_floor {
+                return T::zero();
+            }
+            T::from((numerator / (var_f * var_g).sqrt()).clamp(-1.0, 1.0)).unwrap()
+        })
+        .collect())
+}
+
+// Plain correlation over the valid samples, NaN at lags whose overlap includes an invalid sample
+// of either input
+//
+// Masked-out samples may hold any value. Sums are accumulated in `f64` as in
+// `fft_masked_correlate_1d`, and the invalid counts come from the same transforms.
+pub(crate) fn masked_correlate_1d<T: FftFloat>(
+    signal: &[T],
+    signal_mask: &[bool],
+    template: &[T],
+    template_mask: &[bool],
+    mode: Mode,
+) -> Result<Vec<T>> {
+    masked_segment_correlate_1d(signal, signal_mask, 0..signal.len(), template, template_mask, mode)
+}
+
+// `masked_correlate_1d` of a zero-padded segment of a longer signal: only `support` holds signal
+// samples, so invalid template samples over the padding do not make a lag NaN
+pub(crate) fn masked_segment_correlate_1d<T: FftFloat>(
+    signal: &[T],
+    signal_mask: &[bool],
+    support: Range<usize>,
+    template: &[T],
+    template_mask: &[bool],
+    mode: Mode,
+) -> Result<Vec<T>> {
+    check_mask(signal.len(), signal_mask.len())?;
+    check_mask(template.len(), template_mask.len())?;
+    let len = mode.output_len(signal.len(), template.len());
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let fft_size = FftSizing::default().fft_len(signal.len() + template.len() - 1);
+    let (r2c, c2r) = plan_fft::<f64>(fft_size);
+    let masked = |data: &[T], mask: &[bool]| -> Result<Vec<_>> {
+        let values: Vec<f64> =
+            data.iter().zip(mask).map(|(&v, &valid)| if valid { to_f64(v) } else { 0.0 }).collect();
+        padded_spectrum(&values, fft_size, r2c.as_ref())
+    };
+    let mut cross: Vec<_> = masked(signal, signal_mask)?
+        .iter()
+        .zip(&masked(template, template_mask)?)
+        .map(|(x, y)| x * y.conj())
+        .collect();
+    zero_real_bins(&mut cross, fft_size);
+    let mut values = c2r.make_output_vec();
+    c2r.process(&mut cross, &mut values)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+    let counts = invalid_counts(signal_mask, support, template_mask, fft_size)?;
+
+    let normalization = fft_size as f64;
+    Ok((0..len)
+        .map(|index| {
+            let k = mode.lag_at(index, template.len()).rem_euclid(fft_size as isize) as usize;
+            if counts[k] > 0.5 * normalization {
+                T::nan()
+            } else {
+                T::from(values[k] / normalization).unwrap()
+            }
+        })
+        .collect())
+}
+
+// Lags of `mode` whose overlap includes at least one invalid sample of either input
+pub(crate) fn affected_lags(signal_mask: &[bool], template_mask: &[bool], mode: Mode) -> Result<Vec<bool>> {
+    let (signal_len, template_len) = (signal_mask.len(), template_mask.len());
+    let len = mode.output_len(signal_len, template_len);
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let fft_size = FftSizing::default().fft_len(signal_len + template_len - 1);
+    let counts = invalid_counts(signal_mask, 0..signal_len, template_mask, fft_size)?;
+    let threshold = 0.5 * fft_size as f64;
+    Ok((0..len)
+        .map(|index| counts[mode.lag_at(index, template_len).rem_euclid(fft_size as isize) as usize] > threshold)
+        .collect())
+}
+
+// Circular count of invalid samples in each overlap, scaled by `fft_size`
+//
+// The count is corr(invalid_s, support_s) + corr(1, invalid_t), which is a single inverse FFT of
+// the summed cross-spectra; `support` is the part of the signal holding actual samples
+fn invalid_counts(
+    signal_mask: &[bool],
+    support: Range<usize>,
+    template_mask: &[bool],
+    fft_size: usize,
+) -> Result<Vec<f64>> {
+    let (r2c, c2r) = plan_fft::<f64>(fft_size);
+    let indicator = |flags: &mut dyn Iterator<Item = bool>| -> Result<Vec<_>> {
+        let values: Vec<f64> = flags.map(|flag| if flag { 1.0 } else { 0.0 }).collect();
+        padded_spectrum(&values, fft_size, r2c.as_ref())
+    };
+    let signal_invalid = indicator(&mut signal_mask.iter().map(|&valid| !valid))?;
+    let template_invalid = indicator(&mut template_mask.iter().map(|&valid| !valid))?;
+    let signal_ones = indicator(&mut (0..signal_mask.len()).map(|i| support.contains(&i)))?;
+    let template_ones = indicator(&mut std::iter::repeat_n(true, template_mask.len()))?;
+
+    let mut cross: Vec<_> = (0..signal_invalid.len())
+        .map(|k| signal_invalid[k] * template_ones[k].conj() + signal_ones[k] * template_invalid[k].conj())
+        .collect();
+    zero_real_bins(&mut cross, fft_size);
+    let mut counts = c2r.make_output_vec();
+    c2r.process(&mut cross, &mut counts)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+    Ok(counts)
+}
+
+fn check_mask(data_len: usize, mask_len: usize) -> Result<()> {
+    if data_len != mask_len {
+        return Err(FftCorrelationError::MaskLengthMismatch { data_len, mask_len });
+    }
+    Ok(())
+}
+
+fn to_f64<T: FftFloat>(v: T) -> f64 {
+    v.to_f64().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_normalized_correlate_1d;
+
+    fn naive_masked(
+        signal: &[f64],
+        signal_mask: &[bool],
+        template: &[f64],
+        template_mask: &[bool],
+        mode: Mode,
+        kind: NccKind,
+    ) -> Vec<f64> {
+        (0..mode.output_len(signal.len(), template.len()))
+            .map(|index| {
+                let lag = mode.lag_at(index, template.len());
+                let pairs: Vec<(f64, f64)> = (0..template.len())
+                    .filter_map(|i| {
+                        let s = lag + i as isize;
+                        (s >= 0 && (s as usize) < signal.len() && signal_mask[s as usize] && template_mask[i])
+                            .then(|| (signal[s as usize], template[i]))
+                    })
+                    .collect();
+                let n = pairs.len() as f64;
+                let (mean_f, mean_g) = match kind {
+                    NccKind::Cosine => (0.0, 0.0),
+                    NccKind::Pearson if n > 0.0 => {
+                        (pairs.iter().map(|p| p.0).sum::<f64>() / n, pairs.iter().map(|p| p.1).sum::<f64>() / n)
+                    }
+                    NccKind::Pearson => (0.0, 0.0),
+                };
+                let num: f64 = pairs.iter().map(|(f, g)| (f - mean_f) * (g - mean_g)).sum();
+                let var_f: f64 = pairs.iter().map(|(f, _)| (f - mean_f).powi(2)).sum();
+                let var_g: f64 = pairs.iter().map(|(_, g)| (g - mean_g).powi(2)).sum();
+                if var_f < 1e-9 || var_g < 1e-9 {
+                    0.0
+                } else {
+                    num / (var_f * var_g).sqrt()
+                }
+            })
+            .collect()
+    }
+
+    fn test_signal(len: usize, seed: f64) -> Vec<f64> {
+        (0..len).map(|i| ((i as f64) * seed).sin() + 0.5 * ((i as f64) * seed * 2.3).cos() + 0.01 * i as f64).collect()
+    }
+
+    fn test_mask(len: usize, every: usize) -> Vec<bool> {
+        (0..len).map(|i| i % every != 1).collect()
+    }
+
+    #[test]
+    fn test_masked_matches_naive() {
+        let signal = test_signal(60, 0.31);
+        let template = test_signal(11, 0.77);
+        let (signal_mask, template_mask) = (test_mask(60, 4), test_mask(11, 5));
+        for kind in [NccKind::Cosine, NccKind::Pearson] {
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                let options = MaskedNccOptions::new(kind);
+                let result =
+                    fft_masked_correlate_1d(&signal, &signal_mask, &template, &template_mask, mode, &options).unwrap();
+                let expected = naive_masked(&signal, &signal_mask, &template, &template_mask, mode, kind);
+                assert_eq!(result.len(), expected.len());
+                for (k, (a, b)) in result.iter().zip(expected.iter()).enumerate() {
+                    assert!((a - b).abs() < 1e-9, "{:?} {:?} index {}: {} vs {}", kind, mode, k, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unmasked_equals_normalized_correlation() {
+        let signal = test_signal(200, 0.13);
+        let template = test_signal(17, 0.41);
+        for kind in [NccKind::Cosine, NccKind::Pearson] {
+            let masked =
+                fft_masked_correlate_1d(&signal, &[true; 200], &template, &[true; 17], Mode::Valid, &MaskedNccOptions::new(kind))
+                    .unwrap();
+            let ncc = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, kind).unwrap();
+            for (a, b) in masked.iter().zip(ncc.iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gap_does_not_bias_match_f32() {
+        let template: Vec<f32> = test_signal(32, 0.5).iter().map(|&v| v as f32).collect();
+        let mut signal: Vec<f32> = test_signal(300, 0.09).iter().map(|&v| v as f32).collect();
+        signal[140..172].copy_from_slice(&template);
+        // A dropout covering a third of the embedded template, filled with garbage
+        let mut mask = vec![true; 300];
+        for i in 150..161 {
+            signal[i] = if i % 2 == 0 { f32::NAN } else { 1e30 };
+            mask[i] = false;
+        }
+        let options = MaskedNccOptions::new(NccKind::Pearson).with_min_overlap(16);
+        let ncc = fft_masked_correlate_1d(&signal, &mask, &template, &[true; 32], Mode::Valid, &options).unwrap();
+        assert!(ncc.iter().all(|v| v.is_finite() && v.abs() <= 1.0));
+        let best = (0..ncc.len()).max_by(|&a, &b| ncc[a].partial_cmp(&ncc[b]).unwrap()).unwrap();
+        assert_eq!(best, 140);
+        assert!((ncc[best] - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_overlap_threshold_and_errors() {
+        let signal = test_signal(20, 0.7);
+        let template = test_signal(5, 0.3);
+        let options = MaskedNccOptions::new(NccKind::Cosine).with_min_overlap(5);
+        let full = fft_masked_correlate_1d(&signal, &[true; 20], &template, &[true; 5], Mode::Full, &options).unwrap();
+        // Only lags with the whole template inside the signal reach 5 overlapping samples
+        assert!(full[..4].iter().chain(full[20..].iter()).all(|&v| v == 0.0));
+        assert!(full[4..20].iter().all(|&v| v != 0.0));
+
+        let none = fft_masked_correlate_1d(&signal, &[false; 20], &template, &[true; 5], Mode::Same, &options).unwrap();
+        assert_eq!(none, vec![0.0; 20]);
+
+        let err = fft_masked_correlate_1d(&signal, &[true; 19], &template, &[true; 5], Mode::Full, &options).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::MaskLengthMismatch { data_len: 20, mask_len: 19 }));
+        assert!(fft_masked_correlate_1d::<f64>(&[], &[], &template, &[true; 5], Mode::Full, &options).unwrap().is_empty());
+    }
+}
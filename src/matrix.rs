@@ -0,0 +1,229 @@
+//! Cross-correlation between every pair of channels
+//!
+//! Array processing (direction finding, TDOA) needs the correlation of each channel with every
+//! other channel. [`fft_cross_correlation_matrix`] transforms each channel exactly once, forms the
+//! `C · (C - 1) / 2` cross-spectra `X_i · conj(X_j)` and inverse-transforms each, instead of the
+//! three FFTs per pair that repeated [`fft_correlate_1d`](crate::fft_correlate_1d) calls would cost.
+//! With the `rayon` feature the transforms run in parallel.
+
+use realfft::num_complex::Complex;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::correlate2d::zero_real_bins;
+use crate::multichannel::{channel_samples, frame_count};
+use crate::{padded_spectrum, plan_fft, ChannelLayout, FftCorrelationError, FftFloat, FftSizing, Mode, Result};
+
+/// Correlations of all channel pairs `(i, j)` with `i < j`
+///
+/// Pair `(i, j)` holds `fft_correlate_1d(channel_i, channel_j, mode)`: channel `i` is the signal
+/// and channel `j` the template, so a peak at lag `l` means channel `j`'s content appears `l`
+/// samples later in channel `i`. All pairs share the same length and lag axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossCorrelationMatrix<T> {
+    channels: usize,
+    channel_len: usize,
+    mode: Mode,
+    values: Vec<Vec<T>>,
+}
+
+impl<T> CrossCorrelationMatrix<T> {
+    /// Number of channels
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Samples per channel
+    pub fn channel_len(&self) -> usize {
+        self.channel_len
+    }
+
+    /// Mode the correlations were trimmed to
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Number of stored pairs, `C · (C - 1) / 2`
+    pub fn pair_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Correlation of channel `i` against channel `j`
+    ///
+    /// Returns `None` unless `i < j < channels`. The reverse pair is the same correlation with the
+    /// lag axis negated, so it is not stored separately.
+    pub fn get(&self, i: usize, j: usize) -> Option<&[T]> {
+        if i >= j || j >= self.channels {
+            return None;
+        }
+        let index = i * (2 * self.channels - i - 1) / 2 + (j - i - 1);
+        Some(&self.values[index])
+    }
+
+    /// All pairs as `((i, j), values)` in row-major order of the upper triangle
+    pub fn pairs(&self) -> impl Iterator<Item = ((usize, usize), &[T])> + '_ {
+        let channels = self.channels;
+        (0..channels)
+            .flat_map(move |i| (i + 1..channels).map(move |j| (i, j)))
+            .zip(self.values.iter().map(Vec::as_slice))
+    }
+
+    /// Signed lag of output index `index`, shared by every pair (see [`Mode::lag_at`])
+    pub fn lag_at(&self, index: usize) -> isize {
+        self.mode.lag_at(index, self.channel_len)
+    }
+
+    /// Output index holding `lag`, or `None` if that lag is outside the output
+    pub fn index_of_lag(&self, lag: isize) -> Option<usize> {
+        self.mode.index_of_lag(lag, self.channel_len, self.channel_len)
+    }
+
+    /// Lag of every output index
+    pub fn lags(&self) -> Vec<isize> {
+        (0..self.mode.output_len(self.channel_len, self.channel_len)).map(|index| self.lag_at(index)).collect()
+    }
+}
+
+/// Correlate every pair of channels in a multichannel buffer
+///
+/// Each channel is transformed once; every pair then costs one spectrum product and one inverse
+/// FFT. Results match `fft_correlate_1d(channel_i, channel_j, mode)` up to round-off.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidChannelCount` if `channels` is zero or does not divide
+/// `samples.len()`, and `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_cross_correlation_matrix, ChannelLayout, Mode};
+///
+/// // Three planar channels; channel 2 is channel 0 delayed by 3 samples
+/// let pulse = |at: usize| (0..32).map(|i| if i == at { 1.0 } else { 0.0 }).collect::<Vec<f64>>();
+/// let samples = [pulse(10), pulse(20), pulse(13)].concat();
+/// let matrix = fft_cross_correlation_matrix(&samples, 3, ChannelLayout::Planar, Mode::Full).unwrap();
+///
+/// let pair = matrix.get(0, 2).unwrap();
+/// let best = (0..pair.len()).max_by(|&a, &b| pair[a].partial_cmp(&pair[b]).unwrap()).unwrap();
+/// assert_eq!(matrix.lag_at(best), -3);
+/// ```
+pub fn fft_cross_correlation_matrix<T: FftFloat>(
+    samples: &[T],
+    channels: usize,
+    layout: ChannelLayout,
+    mode: Mode,
+) -> Result<CrossCorrelationMatrix<T>> {
+    let channel_len = frame_count(samples.len(), channels)?;
+    let pair_count = channels * (channels - 1) / 2;
+    let len = mode.output_len(channel_len, channel_len);
+    if len == 0 {
+        return Ok(CrossCorrelationMatrix { channels, channel_len, mode, values: vec![Vec::new(); pair_count] });
+    }
+
+    let fft_size = FftSizing::default().fft_len(2 * channel_len - 1);
+    let (r2c, c2r) = plan_fft(fft_size);
+
+    #[cfg(feature = "rayon")]
+    let channel_iter = (0..channels).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let channel_iter = 0..channels;
+    let spectra = channel_iter
+        .map(|c| padded_spectrum(&channel_samples(samples, channels, layout, c), fft_size, r2c.as_ref()))
+        .collect::<Result<Vec<Vec<Complex<T>>>>>()?;
+
+    let pairs: Vec<(usize, usize)> = (0..channels).flat_map(|i| (i + 1..channels).map(move |j| (i, j))).collect();
+    #[cfg(feature = "rayon")]
+    let pair_iter = pairs.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let pair_iter = pairs.iter();
+
+    // The circular cross-correlation holds lag l at index l mod fft_size
+    let first_lag = mode.lag_at(0, channel_len);
+    let normalization = T::from_len(fft_size);
+    let values = pair_iter
+        .map(|&(i, j)| {
+            let mut cross: Vec<Complex<T>> = spectra[i].iter().zip(&spectra[j]).map(|(x, y)| x * y.conj()).collect();
+            zero_real_bins(&mut cross, fft_size);
+            let mut circular = c2r.make_output_vec();
+            c2r.process(&mut cross, &mut circular)
+                .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+            Ok((0..len)
+                .map(|index| {
+                    let lag = first_lag + index as isize;
+                    circular[lag.rem_euclid(fft_size as isize) as usize] / normalization
+                })
+                .collect())
+        })
+        .collect::<Result<Vec<Vec<T>>>>()?;
+
+    Ok(CrossCorrelationMatrix { channels, channel_len, mode, values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    fn make_channels(channels: usize, frames: usize) -> Vec<Vec<f64>> {
+        (0..channels)
+            .map(|c| (0..frames).map(|i| ((i + 3 * c) as f64 * 0.29).sin() * (1.0 + 0.2 * c as f64) + (i % (c + 2)) as f64 * 0.05).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_matrix_matches_pairwise_correlation() {
+        let channels = make_channels(5, 37);
+        let planar = channels.concat();
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let matrix = fft_cross_correlation_matrix(&planar, 5, ChannelLayout::Planar, mode).unwrap();
+            assert_eq!(matrix.pair_count(), 10);
+            for ((i, j), values) in matrix.pairs() {
+                let expected = fft_correlate_1d(&channels[i], &channels[j], mode).unwrap();
+                assert_eq!(values, matrix.get(i, j).unwrap());
+                assert_eq!(values.len(), expected.len());
+                for (a, b) in values.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-10, "Pair ({}, {}) {:?}", i, j, mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_matrix_recovers_inter_channel_delays() {
+        let source: Vec<f64> = (0..400).map(|i| ((i * i) as f64 * 0.0007).sin()).collect();
+        let delays = [0usize, 7, 19, 4];
+        let channels: Vec<Vec<f64>> = delays
+            .iter()
+            .map(|&d| (0..300).map(|i| source[i + 50 - d]).collect())
+            .collect();
+        let interleaved: Vec<f64> = (0..300).flat_map(|i| channels.iter().map(move |ch| ch[i])).collect();
+
+        let matrix = fft_cross_correlation_matrix(&interleaved, 4, ChannelLayout::Interleaved, Mode::Same).unwrap();
+        for ((i, j), values) in matrix.pairs() {
+            let best = (0..values.len()).max_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap()).unwrap();
+            assert_eq!(matrix.lag_at(best), delays[i] as isize - delays[j] as isize, "Pair ({}, {})", i, j);
+            assert_eq!(matrix.index_of_lag(matrix.lag_at(best)), Some(best));
+        }
+    }
+
+    #[test]
+    fn test_matrix_lag_metadata_and_bounds() {
+        let samples = [1.0f32; 12];
+        let matrix = fft_cross_correlation_matrix(&samples, 3, ChannelLayout::Planar, Mode::Full).unwrap();
+        assert_eq!((matrix.channels(), matrix.channel_len(), matrix.mode()), (3, 4, Mode::Full));
+        assert_eq!(matrix.lags(), vec![-3, -2, -1, 0, 1, 2, 3]);
+        assert!(matrix.get(1, 1).is_none());
+        assert!(matrix.get(2, 1).is_none());
+        assert!(matrix.get(1, 3).is_none());
+        assert_eq!(matrix.index_of_lag(4), None);
+
+        let single = fft_cross_correlation_matrix(&samples, 1, ChannelLayout::Planar, Mode::Full).unwrap();
+        assert_eq!(single.pair_count(), 0);
+        let empty = fft_cross_correlation_matrix::<f32>(&[], 3, ChannelLayout::Interleaved, Mode::Same).unwrap();
+        assert_eq!(empty.pair_count(), 3);
+        assert!(empty.get(0, 1).unwrap().is_empty());
+        assert!(fft_cross_correlation_matrix(&samples, 5, ChannelLayout::Planar, Mode::Full).is_err());
+    }
+}
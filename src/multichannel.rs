@@ -0,0 +1,222 @@
+//! Correlation of one template against every channel of a multichannel buffer
+//!
+//! Microphone arrays and multichannel recordings arrive either interleaved (frame by frame) or
+//! planar (channel by channel). [`fft_correlate_multichannel`] transforms the template once and
+//! reuses its spectrum for every channel, so each channel only pays for its own forward and
+//! inverse FFT. With the `rayon` feature channels are processed in parallel.
+//!
+//! [`fft_correlate_multichannel_sum`] returns the sum of the per-channel correlations. Correlation
+//! is linear in the signal, so this is computed as a single correlation of the summed channels.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    correlate_with_template_spectrum, fft_correlate_1d, plan_fft, reversed_template_spectrum, FftCorrelationError,
+    FftFloat, FftSizing, Mode, Result,
+};
+
+/// Arrangement of channels in a multichannel sample buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    /// Frame-major: `[c0[0], c1[0], ..., c0[1], c1[1], ...]`
+    Interleaved,
+    /// Channel-major: all of channel 0, then all of channel 1, ...
+    Planar,
+}
+
+/// Correlate every channel of `samples` against `template`
+///
+/// Returns one output per channel, in channel order; each is identical to
+/// `fft_correlate_1d(channel, template, mode)` up to round-off. All channels have
+/// `samples.len() / channels` samples.
+///
+/// If the template is empty, or Valid mode is used with channels shorter than the template, every
+/// channel's output is empty.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidChannelCount` if `channels` is zero or does not divide
+/// `samples.len()`, and `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_multichannel, ChannelLayout, Mode};
+///
+/// // Two interleaved channels; the template appears at frame 1 of channel 0 and frame 3 of channel 1
+/// let samples = [0.0f64, 0.0, 1.0, 0.0, 2.0, 0.0, 0.0, 1.0, 0.0, 2.0];
+/// let outputs = fft_correlate_multichannel(&samples, 2, ChannelLayout::Interleaved, &[1.0, 2.0], Mode::Valid).unwrap();
+/// assert_eq!(outputs.len(), 2);
+/// assert!((outputs[0][1] - 5.0).abs() < 1e-12);
+/// assert!((outputs[1][3] - 5.0).abs() < 1e-12);
+/// ```
+pub fn fft_correlate_multichannel<T: FftFloat>(
+    samples: &[T],
+    channels: usize,
+    layout: ChannelLayout,
+    template: &[T],
+    mode: Mode,
+) -> Result<Vec<Vec<T>>> {
+    let frames = frame_count(samples.len(), channels)?;
+    if mode.output_len(frames, template.len()) == 0 {
+        return Ok(vec![Vec::new(); channels]);
+    }
+
+    let fft_size = FftSizing::default().fft_len(frames + template.len() - 1);
+    let (r2c, c2r) = plan_fft(fft_size);
+    let template_spectrum = reversed_template_spectrum(template, fft_size, r2c.as_ref())?;
+
+    #[cfg(feature = "rayon")]
+    let channel_iter = (0..channels).into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let channel_iter = 0..channels;
+
+    channel_iter
+        .map(|c| {
+            let channel = channel_samples(samples, channels, layout, c);
+            correlate_with_template_spectrum(
+                &channel,
+                template.len(),
+                &template_spectrum,
+                fft_size,
+                r2c.as_ref(),
+                c2r.as_ref(),
+                mode,
+            )
+        })
+        .collect()
+}
+
+/// Sum of the correlations of every channel of `samples` against `template`
+///
+/// Equal to adding up the outputs of [`fft_correlate_multichannel`], but computed with a single
+/// correlation of the channel sum.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidChannelCount` if `channels` is zero or does not divide
+/// `samples.len()`, and `FftCorrelationError::FftProcessing` if FFT processing fails.
+pub fn fft_correlate_multichannel_sum<T: FftFloat>(
+    samples: &[T],
+    channels: usize,
+    layout: ChannelLayout,
+    template: &[T],
+    mode: Mode,
+) -> Result<Vec<T>> {
+    let frames = frame_count(samples.len(), channels)?;
+    let mut summed = vec![T::zero(); frames];
+    match layout {
+        ChannelLayout::Interleaved => {
+            for (sum, frame) in summed.iter_mut().zip(samples.chunks_exact(channels)) {
+                *sum = frame.iter().fold(T::zero(), |acc, &x| acc + x);
+            }
+        }
+        ChannelLayout::Planar => {
+            for channel in samples.chunks_exact(frames.max(1)) {
+                for (sum, &x) in summed.iter_mut().zip(channel) {
+                    *sum += x;
+                }
+            }
+        }
+    }
+    fft_correlate_1d(&summed, template, mode)
+}
+
+// Frames per channel, validating the channel count
+pub(crate) fn frame_count(len: usize, channels: usize) -> Result<usize> {
+    if channels == 0 || !len.is_multiple_of(channels) {
+        return Err(FftCorrelationError::InvalidChannelCount { channels, len });
+    }
+    Ok(len / channels)
+}
+
+// Samples of channel `c`; planar channels are borrowed, interleaved ones gathered
+pub(crate) fn channel_samples<T: FftFloat>(samples: &[T], channels: usize, layout: ChannelLayout, c: usize) -> Cow<'_, [T]> {
+    let frames = samples.len() / channels;
+    match layout {
+        ChannelLayout::Planar => Cow::Borrowed(&samples[c * frames..(c + 1) * frames]),
+        ChannelLayout::Interleaved => Cow::Owned(samples[c..].iter().step_by(channels).copied().collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_channels(channels: usize, frames: usize) -> Vec<Vec<f64>> {
+        (0..channels)
+            .map(|c| (0..frames).map(|i| ((i * (c + 1)) as f64 * 0.13).sin() + c as f64 * 0.1).collect())
+            .collect()
+    }
+
+    fn interleave(channels: &[Vec<f64>]) -> Vec<f64> {
+        let frames = channels[0].len();
+        (0..frames).flat_map(|i| channels.iter().map(move |ch| ch[i])).collect()
+    }
+
+    #[test]
+    fn test_multichannel_matches_per_channel_correlation() {
+        let channels = make_channels(5, 120);
+        let template: Vec<f64> = (0..9).map(|i| (i as f64 * 0.7).cos()).collect();
+        let planar: Vec<f64> = channels.concat();
+        let interleaved = interleave(&channels);
+
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let from_planar = fft_correlate_multichannel(&planar, 5, ChannelLayout::Planar, &template, mode).unwrap();
+            let from_interleaved =
+                fft_correlate_multichannel(&interleaved, 5, ChannelLayout::Interleaved, &template, mode).unwrap();
+            for (c, channel) in channels.iter().enumerate() {
+                let expected = fft_correlate_1d(channel, &template, mode).unwrap();
+                for output in [&from_planar[c], &from_interleaved[c]] {
+                    assert_eq!(output.len(), expected.len());
+                    for (a, b) in output.iter().zip(expected.iter()) {
+                        assert!((a - b).abs() < 1e-12, "Channel {} {:?}", c, mode);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multichannel_sum_matches_summed_outputs() {
+        let channels = make_channels(8, 64);
+        let template: Vec<f64> = vec![0.5, -1.0, 2.0, 0.25];
+        let interleaved = interleave(&channels);
+        let planar = channels.concat();
+
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let per_channel =
+                fft_correlate_multichannel(&interleaved, 8, ChannelLayout::Interleaved, &template, mode).unwrap();
+            let summed = fft_correlate_multichannel_sum(&interleaved, 8, ChannelLayout::Interleaved, &template, mode).unwrap();
+            let summed_planar = fft_correlate_multichannel_sum(&planar, 8, ChannelLayout::Planar, &template, mode).unwrap();
+            for k in 0..summed.len() {
+                let expected: f64 = per_channel.iter().map(|output| output[k]).sum();
+                assert!((summed[k] - expected).abs() < 1e-10);
+                assert!((summed_planar[k] - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multichannel_invalid_channel_counts() {
+        let samples = [0.0f32; 10];
+        for channels in [0, 3] {
+            let err = fft_correlate_multichannel(&samples, channels, ChannelLayout::Planar, &[1.0], Mode::Full).unwrap_err();
+            assert!(matches!(err, FftCorrelationError::InvalidChannelCount { len: 10, .. }));
+            assert!(fft_correlate_multichannel_sum(&samples, channels, ChannelLayout::Interleaved, &[1.0], Mode::Full).is_err());
+        }
+    }
+
+    #[test]
+    fn test_multichannel_empty_outputs() {
+        let samples = [1.0f32; 6];
+        let outputs = fft_correlate_multichannel(&samples, 3, ChannelLayout::Interleaved, &[1.0; 3], Mode::Valid).unwrap();
+        assert_eq!(outputs, vec![Vec::<f32>::new(); 3]);
+        let outputs = fft_correlate_multichannel::<f32>(&[], 4, ChannelLayout::Planar, &[1.0], Mode::Full).unwrap();
+        assert_eq!(outputs.len(), 4);
+        assert!(outputs.iter().all(|o| o.is_empty()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Normalized cross-correlation (NCC) for template matching
+//!
+//! Raw correlation values scale with signal energy, so a fixed peak threshold means different
+//! things in loud and quiet recordings. Normalized correlation divides every lag by the norm of
+//! the signal samples under the template window and by the template norm, giving values in
+//! `[-1, 1]` regardless of signal level.
+//!
+//! The numerator is a single FFT correlation. Local window energies are computed from prefix sums
+//! in O(N + M), so the whole computation stays O((N + M) log(N + M)).
+//!
+//! Samples outside the signal are treated as zeros, matching the zero padding of
+//! [`fft_correlate_1d`]. In Full and Same modes, edge lags therefore normalize over a window that
+//! partly lies in the padding.
+
+use crate::{fft_correlate_1d, trim_full_output, FftFloat, Mode, Result};
+
+/// Normalization applied by [`fft_normalized_correlate_1d`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NccKind {
+    /// Cosine similarity between the template and each signal window:
+    /// `sum(x * t) / (||x|| * ||t||)`
+    Cosine,
+    /// Pearson correlation coefficient: both the template and each signal window are made
+    /// zero-mean before normalizing, so constant offsets do not affect the result
+    Pearson,
+}
+
+/// Normalized cross-correlation of two 1D signals using FFT
+///
+/// Output length and indexing follow [`Mode`] exactly as in [`fft_correlate_1d`]: in Full mode,
+/// index `k` is the lag where `template[template.len()-1]` aligns with `signal[k]`.
+///
+/// Every value lies in `[-1, 1]`. Lags where the signal window or the template has (numerically)
+/// zero energy or variance are reported as `0`, since the correlation is undefined there. A window
+/// counts as numerically silent when its norm is within the FFT round-off of the numerator, about
+/// `T::epsilon() * log2(N + M)` times the norm of the whole signal; quieter windows than that
+/// cannot be told apart from round-off.
+///
+/// Returns an empty vector if either input is empty or if Valid mode is used
+/// with signal shorter than template.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_normalized_correlate_1d, Mode, NccKind};
+///
+/// let template = [1.0f32, 3.0, 2.0];
+/// let signal = [0.0f32, 0.0, 10.0, 30.0, 20.0, 0.0];
+/// let ncc = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, NccKind::Cosine).unwrap();
+/// assert!((ncc[2] - 1.0).abs() < 1e-5);
+/// ```
+pub fn fft_normalized_correlate_1d<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    mode: Mode,
+    kind: NccKind,
+) -> Result<Vec<T>> {
+    if signal.is_empty() || template.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let template_len = template.len();
+    let m = template_len as f64;
+
+    // Center the template for Pearson; sum(x_w * (t - mean_t)) equals the
+    // covariance numerator because the centered template sums to zero
+    let template_mean = match kind {
+        NccKind::Cosine => T::zero(),
+        NccKind::Pearson => template.iter().fold(T::zero(), |acc, &v| acc + v) / T::from_len(template_len),
+    };
+    let template_centered: Vec<T> = template.iter().map(|&v| v - template_mean).collect();
+    let template_norm = template_centered
+        .iter()
+        .map(|v| to_f64(*v).powi(2))
+        .sum::<f64>()
+        .sqrt();
+
+    let numerator = fft_correlate_1d(signal, &template_centered, Mode::Full)?;
+
+    // Prefix sums over the signal (accumulated in f64 to limit cancellation)
+    let mut prefix_sum = Vec::with_capacity(signal.len() + 1);
+    let mut prefix_sq = Vec::with_capacity(signal.len() + 1);
+    prefix_sum.push(0.0);
+    prefix_sq.push(0.0);
+    for &v in signal {
+        let v = to_f64(v);
+        prefix_sum.push(prefix_sum.last().unwrap() + v);
+        prefix_sq.push(prefix_sq.last().unwrap() + v * v);
+    }
+
+    // Full index k covers signal[k + 1 - M ..= k], clipped to the signal. Differences of prefix
+    // sums lose about f64::EPSILON of everything accumulated so far, so energies below that are zero.
+    let window_norms: Vec<f64> = (0..numerator.len())
+        .map(|k| {
+            let end = (k + 1).min(signal.len());
+            let start = (k + 1).saturating_sub(template_len);
+            let sum_sq = prefix_sq[end] - prefix_sq[start];
+            let energy = match kind {
+                NccKind::Cosine => sum_sq,
+                NccKind::Pearson => {
+                    let sum = prefix_sum[end] - prefix_sum[start];
+                    sum_sq - sum * sum / m
+                }
+            };
+            let cancellation = 2.0 * end as f64 * f64::EPSILON * prefix_sq[end];
+            if energy <= cancellation { 0.0 } else { energy.sqrt() }
+        })
+        .collect();
+
+    // Round-off in the FFT numerator is about eps * log2(n) * ||signal|| * ||template|| at every
+    // lag, so windows whose normalized value would be dominated by it count as having no energy
+    let signal_norm = prefix_sq[signal.len()].sqrt();
+    let log_len = (numerator.len() as f64).log2().max(1.0);
+    let threshold = 4.0 * to_f64(T::epsilon()) * log_len * signal_norm * template_norm;
+
+    let full: Vec<T> = numerator
+        .iter()
+        .zip(window_norms.iter())
+        .map(|(&num, &window_norm)| {
+            let denominator = window_norm * template_norm;
+            if denominator <= threshold {
+                T::zero()
+            } else {
+                T::from((to_f64(num) / denominator).clamp(-1.0, 1.0)).unwrap()
+            }
+        })
+        .collect();
+
+    Ok(trim_full_output(full, signal.len(), template_len, mode))
+}
+
+fn to_f64<T: FftFloat>(v: T) -> f64 {
+    v.to_f64().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Naive normalized correlation over the zero-padded signal, used for correctness checks.
+    fn naive_full_ncc(signal: &[f64], template: &[f64], kind: NccKind) -> Vec<f64> {
+        let m = template.len();
+        let t_mean = match kind {
+            NccKind::Cosine => 0.0,
+            NccKind::Pearson => template.iter().sum::<f64>() / m as f64,
+        };
+        let t: Vec<f64> = template.iter().map(|v| v - t_mean).collect();
+        let t_norm = t.iter().map(|v| v * v).sum::<f64>().sqrt();
+
+        (0..signal.len() + m - 1)
+            .map(|k| {
+                let window: Vec<f64> = (0..m)
+                    .map(|i| {
+                        let idx = k as isize - (m as isize - 1) + i as isize;
+                        if (0..signal.len() as isize).contains(&idx) { signal[idx as usize] } else { 0.0 }
+                    })
+                    .collect();
+                let w_mean = match kind {
+                    NccKind::Cosine => 0.0,
+                    NccKind::Pearson => window.iter().sum::<f64>() / m as f64,
+                };
+                let w: Vec<f64> = window.iter().map(|v| v - w_mean).collect();
+                let w_norm = w.iter().map(|v| v * v).sum::<f64>().sqrt();
+                let num: f64 = w.iter().zip(t.iter()).map(|(a, b)| a * b).sum();
+                if w_norm * t_norm < 1e-12 { 0.0 } else { num / (w_norm * t_norm) }
+            })
+            .collect()
+    }
+
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| ((i as f64) * 0.37).sin() * 3.0 + ((i as f64) * 1.3).cos() + 0.5).collect()
+    }
+
+    #[test]
+    fn test_ncc_matches_naive_across_modes() {
+        let signal = test_signal(40);
+        let template: Vec<f64> = vec![0.3, -1.2, 2.0, 0.7, -0.4];
+
+        for kind in [NccKind::Cosine, NccKind::Pearson] {
+            let expected = naive_full_ncc(&signal, &template, kind);
+            let full = fft_normalized_correlate_1d(&signal, &template, Mode::Full, kind).unwrap();
+            assert_eq!(full.len(), expected.len());
+            for (i, (a, b)) in full.iter().zip(expected.iter()).enumerate() {
+                assert!((a - b).abs() < 1e-9, "{:?} mismatch at {}: {} vs {}", kind, i, a, b);
+            }
+
+            let same = fft_normalized_correlate_1d(&signal, &template, Mode::Same, kind).unwrap();
+            let start = (expected.len() - signal.len()) / 2;
+            assert_eq!(same.len(), signal.len());
+            for (a, b) in same.iter().zip(expected[start..].iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+
+            let valid = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, kind).unwrap();
+            assert_eq!(valid.len(), signal.len() - template.len() + 1);
+            for (a, b) in valid.iter().zip(expected[template.len() - 1..].iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ncc_exact_match_peaks_at_one() {
+        let template: Vec<f32> = vec![0.5, -1.0, 2.0, 1.5, -0.5];
+        let mut signal: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.9).sin() * 0.2).collect();
+        for (i, &v) in template.iter().enumerate() {
+            // Scaled and offset copy: Pearson should still report a perfect match
+            signal[30 + i] = v * 7.0 + 3.0;
+        }
+
+        let pearson = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, NccKind::Pearson).unwrap();
+        let (peak_idx, peak) = pearson
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap();
+        assert_eq!(peak_idx, 30);
+        assert!((peak - 1.0).abs() < 1e-4, "Pearson peak should be 1, got {}", peak);
+
+        for &v in &pearson {
+            assert!((-1.0..=1.0).contains(&v), "NCC value {} out of range", v);
+        }
+    }
+
+    #[test]
+    fn test_ncc_scale_invariance() {
+        let signal = test_signal(50);
+        let template: Vec<f64> = signal[10..18].to_vec();
+        let scaled: Vec<f64> = signal.iter().map(|v| v * 1000.0).collect();
+
+        for kind in [NccKind::Cosine, NccKind::Pearson] {
+            let a = fft_normalized_correlate_1d(&signal, &template, Mode::Same, kind).unwrap();
+            let b = fft_normalized_correlate_1d(&scaled, &template, Mode::Same, kind).unwrap();
+            for (x, y) in a.iter().zip(b.iter()) {
+                assert!((x - y).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ncc_constant_window_is_zero() {
+        // Pearson is undefined for zero-variance windows; they report 0
+        let signal: Vec<f64> = vec![2.0; 20];
+        let template: Vec<f64> = vec![1.0, 2.0, 3.0];
+        let valid = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, NccKind::Pearson).unwrap();
+        assert!(valid.iter().all(|&v| v == 0.0));
+
+        // Zero template has no defined normalization either
+        let valid = fft_normalized_correlate_1d(&signal, &[0.0; 3], Mode::Valid, NccKind::Cosine).unwrap();
+        assert!(valid.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_ncc_finds_match_60_db_below_loud_section() {
+        let template: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.45).sin() + 0.5 * ((i as f32) * 1.9).cos()).collect();
+        let mut signal: Vec<f32> = (0..4096).map(|i| ((i as f32) * 0.731).sin() + 0.4 * ((i as f32) * 2.3).cos()).collect();
+        // The second half is 60 dB quieter and holds the only copy of the template
+        for (i, v) in signal.iter_mut().enumerate().skip(2048) {
+            *v = if (3000..3064).contains(&i) { template[i - 3000] } else { *v } * 1e-3;
+        }
+
+        for kind in [NccKind::Cosine, NccKind::Pearson] {
+            let ncc = fft_normalized_correlate_1d(&signal, &template, Mode::Valid, kind).unwrap();
+            assert!((ncc[3000] - 1.0).abs() < 1e-2, "{:?} match scored {}", kind, ncc[3000]);
+            let (peak, _) = ncc.iter().enumerate().fold((0, f32::MIN), |best, (i, &v)| if v > best.1 { (i, v) } else { best });
+            assert_eq!(peak, 3000, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_ncc_empty_inputs() {
+        assert!(fft_normalized_correlate_1d::<f32>(&[], &[1.0], Mode::Full, NccKind::Cosine).unwrap().is_empty());
+        assert!(fft_normalized_correlate_1d::<f32>(&[1.0], &[], Mode::Full, NccKind::Pearson).unwrap().is_empty());
+        assert!(fft_normalized_correlate_1d::<f32>(&[1.0], &[1.0, 2.0], Mode::Valid, NccKind::Cosine).unwrap().is_empty());
+    }
+}
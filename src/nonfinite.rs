@@ -0,0 +1,189 @@
+//! Policies for NaN and infinite input samples
+//!
+//! A single NaN or infinity spreads through the FFT to every frequency bin, so it either poisons
+//! every output lag, not just the lags whose window contains it, or makes the inverse transform
+//! reject the spectrum outright. [`fft_correlate_1d_with_policy`] lets the caller choose between
+//! propagating (the behavior of [`fft_correlate_1d`]), rejecting the input, zeroing the offending
+//! samples, or masking them so that only the lags touching them are invalidated. Masking runs the
+//! masked correlation of [`masked`](crate::masked) with the non-finite samples marked invalid.
+
+use std::borrow::Cow;
+
+use crate::masked::masked_correlate_1d;
+use crate::validate::check_finite;
+use crate::{fft_correlate_1d, FftFloat, Mode, Result};
+
+/// Treatment of NaN and infinite samples in [`fft_correlate_1d_with_policy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Correlate as-is, exactly like [`fft_correlate_1d`]: any non-finite sample makes every
+    /// output lag non-finite or fails with `FftCorrelationError::FftProcessing`
+    #[default]
+    Propagate,
+    /// Fail with `FftCorrelationError::NonFinite` naming the first offending sample
+    Reject,
+    /// Replace non-finite samples with zero
+    Zero,
+    /// Treat non-finite samples as missing: lags whose overlap includes one are NaN, all other
+    /// lags are exact
+    Mask,
+}
+
+/// Correlate two 1D signals using FFT with an explicit policy for non-finite samples
+///
+/// Output length and indexing follow [`Mode`] exactly as in [`fft_correlate_1d`]. Inputs without
+/// non-finite samples give the same result under every policy.
+///
+/// With [`NonFinitePolicy::Mask`], non-finite samples are masked out and the correlation runs
+/// over the remaining samples, as in [`fft_masked_correlate_1d`](crate::fft_masked_correlate_1d)
+/// but without normalization. The same pass counts the masked samples under each lag, and lags
+/// that overlap one are set to NaN, so downstream peak picking such as
+/// [`find_max_peak`](crate::find_max_peak), which skips non-finite values, still sees every
+/// unaffected lag. Sums are accumulated in `f64`, so unaffected lags match
+/// [`NonFinitePolicy::Zero`] up to round-off.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::NonFinite` under [`NonFinitePolicy::Reject`] if either input
+/// contains NaN or infinity, and `FftCorrelationError::FftProcessing` if FFT processing fails,
+/// which [`NonFinitePolicy::Propagate`] can trigger on non-finite input.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d_with_policy, Mode, NonFinitePolicy};
+///
+/// let signal = [1.0f64, 2.0, f64::NAN, 4.0, 5.0, 6.0, 7.0];
+/// let template = [1.0, 1.0];
+/// let masked = fft_correlate_1d_with_policy(&signal, &template, Mode::Valid, NonFinitePolicy::Mask).unwrap();
+/// // Only the two windows containing the NaN are invalidated
+/// assert!(masked[1].is_nan() && masked[2].is_nan());
+/// assert!((masked[0] - 3.0).abs() < 1e-12);
+/// assert!((masked[5] - 13.0).abs() < 1e-12);
+/// ```
+pub fn fft_correlate_1d_with_policy<T: FftFloat>(
+    signal: &[T],
+    template: &[T],
+    mode: Mode,
+    policy: NonFinitePolicy,
+) -> Result<Vec<T>> {
+    match policy {
+        NonFinitePolicy::Propagate => fft_correlate_1d(signal, template, mode),
+        NonFinitePolicy::Reject => {
+            check_finite(signal, template)?;
+            fft_correlate_1d(signal, template, mode)
+        }
+        NonFinitePolicy::Zero => fft_correlate_1d(&zero_non_finite(signal), &zero_non_finite(template), mode),
+        NonFinitePolicy::Mask => match finite_masks(signal, template) {
+            Some((signal_mask, template_mask)) => {
+                masked_correlate_1d(signal, &signal_mask, template, &template_mask, mode)
+            }
+            None => fft_correlate_1d(signal, template, mode),
+        },
+    }
+}
+
+// Masks of the finite samples of both inputs for `NonFinitePolicy::Mask`, or `None` if every
+// sample is finite
+pub(crate) fn finite_masks<T: FftFloat>(signal: &[T], template: &[T]) -> Option<(Vec<bool>, Vec<bool>)> {
+    let finite = |data: &[T]| data.iter().all(|v| v.is_finite());
+    if finite(signal) && finite(template) {
+        return None;
+    }
+    let valid = |data: &[T]| data.iter().map(|v| v.is_finite()).collect();
+    Some((valid(signal), valid(template)))
+}
+
+pub(crate) fn zero_non_finite<T: FftFloat>(data: &[T]) -> Cow<'_, [T]> {
+    if data.iter().all(|v| v.is_finite()) {
+        return Cow::Borrowed(data);
+    }
+    Cow::Owned(data.iter().map(|&v| if v.is_finite() { v } else { T::zero() }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FftCorrelationError, InputKind};
+
+    const POLICIES: [NonFinitePolicy; 4] =
+        [NonFinitePolicy::Propagate, NonFinitePolicy::Reject, NonFinitePolicy::Zero, NonFinitePolicy::Mask];
+
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (i as f64 * 0.37).sin() + 0.5).collect()
+    }
+
+    #[test]
+    fn test_finite_input_is_policy_independent() {
+        let signal = test_signal(40);
+        let template = [0.5, -1.0, 0.25];
+        let expected = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
+        for policy in POLICIES {
+            assert_eq!(fft_correlate_1d_with_policy(&signal, &template, Mode::Same, policy).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_propagate_reject_and_zero() {
+        let mut signal = test_signal(30);
+        signal[12] = f64::INFINITY;
+        signal[20] = f64::NAN;
+        let template = [1.0, 2.0, 3.0];
+
+        // Non-finite spectra are either rejected by the inverse FFT or poison every lag
+        match fft_correlate_1d_with_policy(&signal, &template, Mode::Full, NonFinitePolicy::Propagate) {
+            Ok(propagated) => assert!(propagated.iter().all(|v| !v.is_finite())),
+            Err(err) => assert!(matches!(err, FftCorrelationError::FftProcessing(_))),
+        }
+
+        let err = fft_correlate_1d_with_policy(&signal, &template, Mode::Full, NonFinitePolicy::Reject).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::NonFinite { input: InputKind::Signal, index: 12 }));
+
+        let zeroed = fft_correlate_1d_with_policy(&signal, &template, Mode::Full, NonFinitePolicy::Zero).unwrap();
+        let mut cleaned = signal.clone();
+        cleaned[12] = 0.0;
+        cleaned[20] = 0.0;
+        assert_eq!(zeroed, fft_correlate_1d(&cleaned, &template, Mode::Full).unwrap());
+    }
+
+    #[test]
+    fn test_mask_invalidates_only_overlapping_lags() {
+        let mut signal = test_signal(50);
+        signal[10] = f64::NAN;
+        let template = [0.3, -0.7, 1.1, 0.4];
+
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let masked = fft_correlate_1d_with_policy(&signal, &template, mode, NonFinitePolicy::Mask).unwrap();
+            let zeroed = fft_correlate_1d_with_policy(&signal, &template, mode, NonFinitePolicy::Zero).unwrap();
+            for (index, (&m, &z)) in masked.iter().zip(zeroed.iter()).enumerate() {
+                // signal[10] lies under the template for lags 7..=10
+                let lag = mode.lag_at(index, template.len());
+                if (7..=10).contains(&lag) {
+                    assert!(m.is_nan(), "{:?} lag {}", mode, lag);
+                } else {
+                    assert!((m - z).abs() < 1e-12, "{:?} lag {}: {} vs {}", mode, lag, m, z);
+                }
+            }
+        }
+
+        // A bad template sample touches every lag where it overlaps the signal
+        let short = test_signal(6);
+        let template = [1.0, f64::NAN, 1.0];
+        let masked = fft_correlate_1d_with_policy(&short, &template, Mode::Full, NonFinitePolicy::Mask).unwrap();
+        let lags_hit: Vec<isize> = (0..masked.len()).filter(|&k| masked[k].is_nan()).map(|k| Mode::Full.lag_at(k, 3)).collect();
+        assert_eq!(lags_hit, vec![-1, 0, 1, 2, 3, 4]);
+        assert!(masked[0].is_finite() && masked[7].is_finite());
+    }
+
+    #[test]
+    fn test_mask_keeps_peak_away_from_dropout_f32() {
+        let template: Vec<f32> = (0..16).map(|i| (i as f32 * 0.9).sin()).collect();
+        let mut signal = vec![0.0f32; 400];
+        signal[300..316].copy_from_slice(&template);
+        signal[50] = f32::NAN;
+        let masked = fft_correlate_1d_with_policy(&signal, &template, Mode::Valid, NonFinitePolicy::Mask).unwrap();
+        assert_eq!(masked.iter().filter(|v| v.is_nan()).count(), 16);
+        let best = crate::find_max_peak(&masked, template.len(), Mode::Valid).unwrap();
+        assert_eq!(best.index, 300);
+    }
+}
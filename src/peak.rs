@@ -0,0 +1,245 @@
+//! Peak detection and lag estimation on correlation output
+//!
+//! [`find_peaks`] locates local maxima in the output of [`fft_correlate_1d`](crate::fft_correlate_1d)
+//! (or any function sharing its [`Mode`] indexing), skips non-finite values, and converts each
+//! output index to a signed lag with [`Mode::lag_at`]. Peaks are reported with their value and
+//! topographic prominence, the same measure used by `scipy.signal.peak_prominences`.
+
+use crate::{FftFloat, Mode};
+
+/// A local maximum in correlation output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Peak<T> {
+    /// Index into the correlation output
+    pub index: usize,
+    /// Signed lag of the peak; `template[0]` aligns with `signal[lag]`
+    pub lag: isize,
+    /// Correlation value at the peak
+    pub value: T,
+    /// Height of the peak above the higher of its two surrounding minima
+    pub prominence: T,
+}
+
+/// Selection criteria for [`find_peaks`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeakOptions<T> {
+    /// Maximum number of peaks to return (the highest ones are kept); `None` returns all
+    pub max_peaks: Option<usize>,
+    /// Minimum index distance between returned peaks; higher peaks win ties
+    pub min_distance: usize,
+    /// Minimum peak value; peaks below it are discarded
+    pub threshold: Option<T>,
+    /// Minimum prominence; peaks less prominent than this are discarded
+    pub min_prominence: Option<T>,
+}
+
+impl<T> Default for PeakOptions<T> {
+    fn default() -> Self {
+        Self {
+            max_peaks: None,
+            min_distance: 1,
+            threshold: None,
+            min_prominence: None,
+        }
+    }
+}
+
+/// Find local maxima in correlation output
+///
+/// A sample is a peak if it is finite and strictly greater than its nearest finite neighbours on
+/// both sides. Flat tops count once, at their middle sample (left-biased for even widths). The
+/// first and last samples can be peaks, since the best lag often sits at the edge of Valid
+/// output. Non-finite values (NaN/Inf) are ignored: they are never peaks and never block one.
+///
+/// Peaks are returned sorted by descending value. `template_len` and `mode` must be the ones that
+/// produced `values`; they determine each peak's lag.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d, find_peaks, Mode, PeakOptions};
+///
+/// let template = [0.5f32, 1.0, 0.5];
+/// let mut signal = vec![0.0f32; 64];
+/// signal[20..23].copy_from_slice(&template);
+/// signal[45..48].copy_from_slice(&[0.25, 0.5, 0.25]);
+///
+/// let result = fft_correlate_1d(&signal, &template, Mode::Same).unwrap();
+/// let options = PeakOptions { max_peaks: Some(2), min_distance: 5, ..Default::default() };
+/// let peaks = find_peaks(&result, template.len(), Mode::Same, &options);
+/// assert_eq!(peaks[0].lag, 20);
+/// assert_eq!(peaks[1].lag, 45);
+/// ```
+pub fn find_peaks<T: FftFloat>(
+    values: &[T],
+    template_len: usize,
+    mode: Mode,
+    options: &PeakOptions<T>,
+) -> Vec<Peak<T>> {
+    let mut candidates: Vec<usize> = local_maxima(values)
+        .into_iter()
+        .filter(|&i| options.threshold.is_none_or(|t| values[i] >= t))
+        .collect();
+    candidates.sort_by(|&a, &b| values[b].partial_cmp(&values[a]).unwrap().then(a.cmp(&b)));
+
+    let mut peaks: Vec<Peak<T>> = Vec::new();
+    for index in candidates {
+        if options.max_peaks.is_some_and(|k| peaks.len() >= k) {
+            break;
+        }
+        if peaks.iter().any(|p| p.index.abs_diff(index) < options.min_distance) {
+            continue;
+        }
+        let prominence = prominence(values, index);
+        if options.min_prominence.is_some_and(|p| prominence < p) {
+            continue;
+        }
+        peaks.push(Peak {
+            index,
+            lag: mode.lag_at(index, template_len),
+            value: values[index],
+            prominence,
+        });
+    }
+    peaks
+}
+
+/// Highest finite value in correlation output as a [`Peak`]
+///
+/// Unlike [`find_peaks`], this does not require a local maximum, so it always returns a result
+/// unless every value is non-finite or `values` is empty.
+pub fn find_max_peak<T: FftFloat>(values: &[T], template_len: usize, mode: Mode) -> Option<Peak<T>> {
+    let (index, &value) = values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.is_finite())
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())?;
+    Some(Peak {
+        index,
+        lag: mode.lag_at(index, template_len),
+        value,
+        prominence: prominence(values, index),
+    })
+}
+
+// Indices of finite local maxima; plateaus report their middle sample
+fn local_maxima<T: FftFloat>(values: &[T]) -> Vec<usize> {
+    let finite: Vec<usize> = (0..values.len()).filter(|&i| values[i].is_finite()).collect();
+    let mut maxima = Vec::new();
+
+    let mut i = 0;
+    while i < finite.len() {
+        // Extend over a run of equal finite values
+        let mut j = i;
+        while j + 1 < finite.len() && values[finite[j + 1]] == values[finite[i]] {
+            j += 1;
+        }
+        let value = values[finite[i]];
+        let left_lower = i == 0 || values[finite[i - 1]] < value;
+        let right_lower = j + 1 == finite.len() || values[finite[j + 1]] < value;
+        if left_lower && right_lower {
+            maxima.push(finite[(i + j) / 2]);
+        }
+        i = j + 1;
+    }
+    maxima
+}
+
+// Topographic prominence: walk outwards until a strictly higher value or the edge, track the
+// lowest value on each side, and measure from the higher of the two minima. A side with no
+// finite samples (the peak sits at an edge) does not constrain the base.
+fn prominence<T: FftFloat>(values: &[T], index: usize) -> T {
+    let peak = values[index];
+    let side_min = |side: &mut dyn Iterator<Item = &T>| -> Option<T> {
+        let mut side = side.filter(|v| v.is_finite()).peekable();
+        side.peek()?;
+        Some(side.take_while(|&&v| v <= peak).fold(peak, |acc, &v| acc.min(v)))
+    };
+
+    let left_min = side_min(&mut values[..index].iter().rev());
+    let right_min = side_min(&mut values[index + 1..].iter());
+    match (left_min, right_min) {
+        (Some(l), Some(r)) => peak - l.max(r),
+        (Some(base), None) | (None, Some(base)) => peak - base,
+        (None, None) => T::zero(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    #[test]
+    fn test_find_peaks_top_k_with_separation() {
+        let values: Vec<f64> = vec![0.0, 5.0, 4.9, 0.0, 3.0, 0.0, 0.0, 4.0, 0.0, 1.0];
+        let all = find_peaks(&values, 1, Mode::Full, &PeakOptions::default());
+        let indices: Vec<usize> = all.iter().map(|p| p.index).collect();
+        assert_eq!(indices, vec![1, 7, 4, 9]);
+
+        let options = PeakOptions { max_peaks: Some(2), min_distance: 4, ..Default::default() };
+        let top = find_peaks(&values, 1, Mode::Full, &options);
+        assert_eq!(top.iter().map(|p| p.index).collect::<Vec<_>>(), vec![1, 7]);
+
+        let options = PeakOptions { min_distance: 4, threshold: Some(3.5), ..Default::default() };
+        let strong = find_peaks(&values, 1, Mode::Full, &options);
+        assert_eq!(strong.iter().map(|p| p.index).collect::<Vec<_>>(), vec![1, 7]);
+    }
+
+    #[test]
+    fn test_find_peaks_prominence() {
+        let values: Vec<f64> = vec![0.0, 3.0, 1.0, 5.0, 2.0, 2.5, 0.5];
+        let peaks = find_peaks(&values, 1, Mode::Full, &PeakOptions::default());
+        let by_index = |i: usize| peaks.iter().find(|p| p.index == i).unwrap().prominence;
+        assert_eq!(by_index(3), 4.5);
+        assert_eq!(by_index(1), 2.0);
+        assert_eq!(by_index(5), 0.5);
+
+        let options = PeakOptions { min_prominence: Some(1.0), ..Default::default() };
+        let prominent = find_peaks(&values, 1, Mode::Full, &options);
+        assert_eq!(prominent.iter().map(|p| p.index).collect::<Vec<_>>(), vec![3, 1]);
+    }
+
+    #[test]
+    fn test_find_peaks_ignores_non_finite_and_handles_plateaus() {
+        let values: Vec<f32> = vec![f32::NAN, 1.0, 2.0, 2.0, 2.0, 1.0, f32::INFINITY, 0.5, f32::NAN];
+        let peaks = find_peaks(&values, 1, Mode::Full, &PeakOptions::default());
+        // 0.5 at index 7 is below its nearest finite neighbour once the Inf is skipped
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].index, 3, "Plateau peak should be reported at its middle");
+        assert_eq!(peaks[0].prominence, 1.0);
+
+        assert!(find_peaks::<f32>(&[f32::NAN; 4], 1, Mode::Full, &PeakOptions::default()).is_empty());
+        assert!(find_max_peak::<f32>(&[f32::NAN; 4], 1, Mode::Full).is_none());
+    }
+
+    #[test]
+    fn test_find_peaks_lag_consistent_across_modes() {
+        let template: Vec<f64> = vec![0.25, 1.0, -0.5, 0.75];
+        let mut signal: Vec<f64> = (0..80).map(|i| ((i as f64) * 1.7).sin() * 0.05).collect();
+        for (i, &v) in template.iter().enumerate() {
+            signal[33 + i] += v * 4.0;
+        }
+
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let result = fft_correlate_1d(&signal, &template, mode).unwrap();
+            let best = find_max_peak(&result, template.len(), mode).unwrap();
+            assert_eq!(best.lag, 33, "{:?} lag mismatch", mode);
+
+            let options = PeakOptions { max_peaks: Some(1), ..Default::default() };
+            let peaks = find_peaks(&result, template.len(), mode, &options);
+            assert_eq!(peaks[0].lag, 33);
+            assert_eq!(peaks[0].index, best.index);
+        }
+    }
+
+    #[test]
+    fn test_find_peaks_edges() {
+        let values: Vec<f64> = vec![5.0, 1.0, 0.0, 1.0, 6.0];
+        let peaks = find_peaks(&values, 1, Mode::Valid, &PeakOptions::default());
+        assert_eq!(peaks.iter().map(|p| p.index).collect::<Vec<_>>(), vec![4, 0]);
+        assert_eq!(peaks[0].prominence, 6.0);
+        assert_eq!(peaks[1].prominence, 5.0);
+        assert!(find_peaks::<f64>(&[], 0, Mode::Full, &PeakOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+//! FFT length selection
+//!
+//! Linear correlation only needs an FFT of at least `N + M - 1` points, and any longer length
+//! works equally well. Rounding up to a power of two is simple but nearly doubles the work for
+//! lengths just above one: a 1025-sample signal and a 64-sample template need 1088 points, which a
+//! power of two pads to 2048. realfft has fast kernels for radices 2, 3, 5 and 7, so the smallest
+//! 7-smooth length (1120 here) is usually faster, like `scipy.fft.next_fast_len`.
+
+/// Policy for choosing the padded FFT length from the minimum linear-correlation length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FftSizing {
+    /// Round up to the next power of two
+    PowerOfTwo,
+    /// Round up with [`next_fast_len`]
+    #[default]
+    Fast,
+}
+
+impl FftSizing {
+    /// Padded FFT length for an output of at least `min_len` samples
+    pub fn fft_len(self, min_len: usize) -> usize {
+        match self {
+            FftSizing::PowerOfTwo => min_len.next_power_of_two(),
+            FftSizing::Fast => next_fast_len(min_len),
+        }
+    }
+}
+
+/// Smallest even 2·3·5·7-smooth length that is at least `n`
+///
+/// A length is 7-smooth if it has no prime factor larger than 7. realfft computes an even-length
+/// real FFT with a complex FFT of half the length, so odd lengths are skipped. Lengths below 2 are
+/// returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::next_fast_len;
+///
+/// assert_eq!(next_fast_len(1025), 1050); // 2 · 3 · 5² · 7
+/// assert_eq!(next_fast_len(2049), 2058); // 2 · 3 · 7³
+/// assert_eq!(next_fast_len(4096), 4096);
+/// ```
+pub fn next_fast_len(n: usize) -> usize {
+    if n <= 2 {
+        return n;
+    }
+    // Enumerate 2 · 3^a · 5^b · 7^c · 2^d, keeping the smallest candidate >= n
+    let half = n.div_ceil(2);
+    let mut best = half.next_power_of_two();
+    let mut p7 = 1usize;
+    while p7 < best {
+        let mut p57 = p7;
+        while p57 < best {
+            let mut p357 = p57;
+            while p357 < best {
+                // Smallest power-of-two multiple of p357 reaching half
+                let candidate = p357 * half.div_ceil(p357).next_power_of_two();
+                best = best.min(candidate);
+                p357 *= 3;
+            }
+            p57 *= 5;
+        }
+        p7 *= 7;
+    }
+    2 * best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_seven_smooth(mut n: usize) -> bool {
+        for p in [2usize, 3, 5, 7] {
+            while n.is_multiple_of(p) {
+                n /= p;
+            }
+        }
+        n == 1
+    }
+
+    #[test]
+    fn test_next_fast_len_matches_brute_force() {
+        for n in 3..5000 {
+            let expected = (n..).find(|&m: &usize| m.is_multiple_of(2) && is_seven_smooth(m)).unwrap();
+            assert_eq!(next_fast_len(n), expected, "n = {}", n);
+        }
+        assert_eq!(next_fast_len(0), 0);
+        assert_eq!(next_fast_len(1), 1);
+        assert_eq!(next_fast_len(2), 2);
+    }
+
+    #[test]
+    fn test_fft_sizing_policies() {
+        assert_eq!(FftSizing::PowerOfTwo.fft_len(1088), 2048);
+        assert_eq!(FftSizing::Fast.fft_len(1088), 1120);
+        assert_eq!(FftSizing::default(), FftSizing::Fast);
+        for n in [1, 17, 1000, 65_537] {
+            assert!(FftSizing::Fast.fft_len(n) <= FftSizing::PowerOfTwo.fft_len(n));
+        }
+    }
+}
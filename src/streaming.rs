@@ -0,0 +1,296 @@
+//! Streaming correlation of unbounded input against a fixed template
+//!
+//! [`StreamingCorrelator`] implements overlap-save: incoming samples are buffered into blocks of
+//! a fixed FFT size `L`, and each block yields `L - M + 1` Valid-mode correlation samples, where
+//! `M` is the template length. The last `M - 1` samples of each block are kept as overlap for the
+//! next one. Concatenating everything returned by [`push`](StreamingCorrelator::push) and
+//! [`flush`](StreamingCorrelator::flush) reproduces `fft_correlate_1d(signal, template, Mode::Valid)`
+//! for the whole stream.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealToComplex};
+use std::sync::Arc;
+
+use crate::{plan_fft, reversed_template_spectrum, FftCorrelationError, FftFloat, Result};
+
+/// Overlap-save correlator producing Valid-mode output from arbitrarily sized pushes
+///
+/// Output sample `n` (counted from the start of the stream) is the correlation of the template
+/// with `stream[n..n + M]`, i.e. Valid-mode index `n` of
+/// [`fft_correlate_1d`](crate::fft_correlate_1d) on the concatenated input.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d, Mode, StreamingCorrelator};
+///
+/// let template = [1.0f32, -1.0, 0.5];
+/// let signal: Vec<f32> = (0..100).map(|i| (i as f32 * 0.3).sin()).collect();
+///
+/// let mut stream = StreamingCorrelator::with_block_size(&template, 16).unwrap();
+/// let mut output = Vec::new();
+/// for chunk in signal.chunks(7) {
+///     output.extend(stream.push(chunk).unwrap());
+/// }
+/// output.extend(stream.flush().unwrap());
+///
+/// let expected = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+/// assert_eq!(output.len(), expected.len());
+/// ```
+pub struct StreamingCorrelator<T: FftFloat> {
+    template_len: usize,
+    block_size: usize,
+    r2c: Arc<dyn RealToComplex<T>>,
+    c2r: Arc<dyn ComplexToReal<T>>,
+    template_spectrum: Vec<Complex<T>>,
+    // Unconsumed input; buffer[0] is the first sample of the next output window
+    buffer: Vec<T>,
+    block: Vec<T>,
+    spectrum: Vec<Complex<T>>,
+    block_output: Vec<T>,
+    samples_emitted: usize,
+}
+
+impl<T: FftFloat> StreamingCorrelator<T> {
+    /// Create a streaming correlator with a block size chosen from the template length
+    ///
+    /// The block size is the next power of two of at least four template lengths (and at least
+    /// 64), which keeps the overlap below a quarter of each block.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::FftProcessing` if the template FFT fails.
+    pub fn new(template: &[T]) -> Result<Self> {
+        let block_size = (4 * template.len()).max(64).next_power_of_two();
+        Self::with_block_size(template, block_size)
+    }
+
+    /// Create a streaming correlator with an explicit FFT block size
+    ///
+    /// Each full block emits `block_size - template.len() + 1` outputs. Larger blocks amortize
+    /// the FFT cost better but add latency; power-of-two sizes are fastest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::InvalidBlockSize` if `block_size < template.len()` or
+    /// `block_size` is zero, and `FftCorrelationError::FftProcessing` if the template FFT fails.
+    pub fn with_block_size(template: &[T], block_size: usize) -> Result<Self> {
+        if block_size == 0 || block_size < template.len() {
+            return Err(FftCorrelationError::InvalidBlockSize {
+                block_size,
+                template_len: template.len(),
+            });
+        }
+
+        let (r2c, c2r) = plan_fft(block_size);
+        let template_spectrum = reversed_template_spectrum(template, block_size, r2c.as_ref())?;
+        let spectrum = r2c.make_output_vec();
+
+        Ok(Self {
+            template_len: template.len(),
+            block_size,
+            r2c,
+            c2r,
+            template_spectrum,
+            buffer: Vec::with_capacity(2 * block_size),
+            block: vec![T::zero(); block_size],
+            spectrum,
+            block_output: vec![T::zero(); block_size],
+            samples_emitted: 0,
+        })
+    }
+
+    /// FFT block size used for each overlap-save step
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Number of new outputs produced by each full block
+    pub fn hop_size(&self) -> usize {
+        self.block_size + 1 - self.template_len.max(1)
+    }
+
+    /// Number of correlation samples emitted so far
+    ///
+    /// This is also the Valid-mode index of the next sample that will be emitted.
+    pub fn samples_emitted(&self) -> usize {
+        self.samples_emitted
+    }
+
+    /// Feed samples and return every correlation sample completed by full blocks
+    ///
+    /// Samples that do not yet fill a block stay buffered until the next push or
+    /// [`flush`](Self::flush). An empty template never produces output, matching
+    /// [`fft_correlate_1d`](crate::fft_correlate_1d).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+    pub fn push(&mut self, samples: &[T]) -> Result<Vec<T>> {
+        if self.template_len == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.buffer.extend_from_slice(samples);
+        let hop = self.hop_size();
+        let mut output = Vec::with_capacity((self.buffer.len() / hop + 1) * hop);
+
+        let mut consumed = 0;
+        while self.buffer.len() - consumed >= self.block_size {
+            self.process_block(consumed, self.block_size, hop, &mut output)?;
+            consumed += hop;
+        }
+        self.buffer.drain(..consumed);
+        Ok(output)
+    }
+
+    /// Emit the outputs still computable from buffered samples
+    ///
+    /// After a flush, the last `template.len() - 1` samples remain buffered, so pushing more
+    /// samples continues the stream seamlessly. Call this at end of input to obtain the tail
+    /// of the Valid-mode output.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+    pub fn flush(&mut self) -> Result<Vec<T>> {
+        if self.template_len == 0 || self.buffer.len() < self.template_len {
+            return Ok(Vec::new());
+        }
+
+        let available = self.buffer.len() - self.template_len + 1;
+        let mut output = Vec::with_capacity(available);
+        self.process_block(0, self.buffer.len(), available, &mut output)?;
+        self.buffer.drain(..available);
+        Ok(output)
+    }
+
+    /// Discard buffered samples and restart the stream
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.samples_emitted = 0;
+    }
+
+    // Correlate buffer[start..start + len] (len <= block_size) and append `count` outputs
+    fn process_block(&mut self, start: usize, len: usize, count: usize, output: &mut Vec<T>) -> Result<()> {
+        self.block[..len].copy_from_slice(&self.buffer[start..start + len]);
+        self.block[len..].iter_mut().for_each(|x| *x = T::zero());
+
+        self.r2c.process(&mut self.block, &mut self.spectrum)
+            .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for stream block: {:?}", e)))?;
+        for (s, t) in self.spectrum.iter_mut().zip(self.template_spectrum.iter()) {
+            *s *= t;
+        }
+        self.c2r.process(&mut self.spectrum, &mut self.block_output)
+            .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed for stream block: {:?}", e)))?;
+
+        // Circular index k holds the window starting at k - (M - 1); indices M-1.. are free of wrap-around
+        let normalization = T::from_len(self.block_size);
+        let first = self.template_len - 1;
+        output.extend(self.block_output[first..first + count].iter().map(|&x| x / normalization));
+        self.samples_emitted += count;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_correlate_1d, Mode};
+
+    fn run_stream<T: FftFloat>(stream: &mut StreamingCorrelator<T>, signal: &[T], chunk: usize) -> Vec<T> {
+        let mut output = Vec::new();
+        for piece in signal.chunks(chunk) {
+            output.extend(stream.push(piece).unwrap());
+        }
+        output.extend(stream.flush().unwrap());
+        output
+    }
+
+    #[test]
+    fn test_streaming_matches_valid_mode() {
+        let signal: Vec<f64> = (0..1000).map(|i| ((i as f64) * 0.13).sin() + ((i as f64) * 0.7).cos() * 0.3).collect();
+        let template: Vec<f64> = (0..37).map(|i| ((i as f64) * 0.4).cos()).collect();
+        let expected = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+
+        for block_size in [37, 38, 64, 100, 256, 2048] {
+            for chunk in [1, 5, 64, 333, 1000] {
+                let mut stream = StreamingCorrelator::with_block_size(&template, block_size).unwrap();
+                let output = run_stream(&mut stream, &signal, chunk);
+                assert_eq!(output.len(), expected.len(), "Length mismatch for block {} chunk {}", block_size, chunk);
+                for (i, (a, b)) in output.iter().zip(expected.iter()).enumerate() {
+                    assert!((a - b).abs() < 1e-9, "Mismatch at {} for block {} chunk {}: {} vs {}", i, block_size, chunk, a, b);
+                }
+                assert_eq!(stream.samples_emitted(), expected.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_streaming_f32_default_block_size() {
+        let signal: Vec<f32> = (0..5000).map(|i| ((i as f32) * 0.01).sin()).collect();
+        let template: Vec<f32> = signal[1200..1300].to_vec();
+        let expected = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+
+        let mut stream = StreamingCorrelator::new(&template).unwrap();
+        assert_eq!(stream.block_size(), 512);
+        let output = run_stream(&mut stream, &signal, 480);
+        assert_eq!(output.len(), expected.len());
+        for (a, b) in output.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-2, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_streaming_emits_full_blocks_before_flush() {
+        let template = [1.0f32, 2.0, 3.0, 4.0];
+        let mut stream = StreamingCorrelator::with_block_size(&template, 8).unwrap();
+        assert_eq!(stream.hop_size(), 5);
+
+        assert!(stream.push(&[1.0; 7]).unwrap().is_empty());
+        assert_eq!(stream.push(&[1.0; 1]).unwrap().len(), 5);
+        // Remaining 3 samples are the overlap; nothing more is computable
+        assert!(stream.flush().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_streaming_flush_then_continue() {
+        let signal: Vec<f64> = (0..200).map(|i| ((i as f64) * 0.21).sin()).collect();
+        let template: Vec<f64> = vec![0.5, -0.25, 1.0, 0.75, -1.0];
+        let expected = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+
+        let mut stream = StreamingCorrelator::with_block_size(&template, 32).unwrap();
+        let mut output = stream.push(&signal[..90]).unwrap();
+        output.extend(stream.flush().unwrap());
+        output.extend(stream.push(&signal[90..]).unwrap());
+        output.extend(stream.flush().unwrap());
+
+        assert_eq!(output.len(), expected.len());
+        for (a, b) in output.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_streaming_invalid_block_size() {
+        let template = [1.0f32; 10];
+        assert!(matches!(
+            StreamingCorrelator::with_block_size(&template, 9),
+            Err(FftCorrelationError::InvalidBlockSize { block_size: 9, template_len: 10 })
+        ));
+        assert!(StreamingCorrelator::<f32>::with_block_size(&[], 0).is_err());
+    }
+
+    #[test]
+    fn test_streaming_empty_template_and_reset() {
+        let mut stream = StreamingCorrelator::<f32>::with_block_size(&[], 8).unwrap();
+        assert!(stream.push(&[1.0; 100]).unwrap().is_empty());
+        assert!(stream.flush().unwrap().is_empty());
+
+        let mut stream = StreamingCorrelator::with_block_size(&[1.0f32, 1.0], 4).unwrap();
+        stream.push(&[1.0; 10]).unwrap();
+        stream.reset();
+        assert_eq!(stream.samples_emitted(), 0);
+        assert!(stream.flush().unwrap().is_empty());
+    }
+}
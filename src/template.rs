@@ -0,0 +1,158 @@
+//! Reusable correlator for matching one template against many signals
+//!
+//! [`fft_correlate_1d`](crate::fft_correlate_1d) pads, reverses and transforms the template on
+//! every call. [`TemplateCorrelator`] does that work once per FFT size and caches the resulting
+//! spectrum, so repeated correlations only pay for the signal's forward FFT and the inverse FFT.
+
+use realfft::num_complex::Complex;
+use std::collections::HashMap;
+
+use crate::{
+    correlate_with_template_spectrum, plan_fft, reversed_template_spectrum, FftFloat, FftSizing, Mode, Result,
+};
+
+/// Correlator holding a fixed template and its cached spectra
+///
+/// Spectra are cached per FFT size, so signals of varying length can be correlated against the
+/// same template. Plans come from the same thread-local planner used by
+/// [`fft_correlate_1d`](crate::fft_correlate_1d), and results are identical to calling
+/// `fft_correlate_1d(signal, template, mode)`.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{Mode, TemplateCorrelator};
+///
+/// let mut correlator = TemplateCorrelator::new(&[0.5, 1.0, 0.5]);
+/// for chunk in [[0.0, 1.0, 2.0, 1.0, 0.0], [1.0, 1.0, 1.0, 1.0, 1.0]] {
+///     let result = correlator.correlate(&chunk, Mode::Valid).unwrap();
+///     assert_eq!(result.len(), 3);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TemplateCorrelator<T: FftFloat> {
+    template: Vec<T>,
+    spectra: HashMap<usize, Vec<Complex<T>>>,
+}
+
+impl<T: FftFloat> TemplateCorrelator<T> {
+    /// Create a correlator for `template`
+    ///
+    /// No FFT work is done until the first call to [`correlate`](Self::correlate).
+    pub fn new(template: &[T]) -> Self {
+        Self {
+            template: template.to_vec(),
+            spectra: HashMap::new(),
+        }
+    }
+
+    /// The template this correlator matches against
+    pub fn template(&self) -> &[T] {
+        &self.template
+    }
+
+    /// Number of template samples
+    pub fn len(&self) -> usize {
+        self.template.len()
+    }
+
+    /// Whether the template is empty
+    pub fn is_empty(&self) -> bool {
+        self.template.is_empty()
+    }
+
+    /// Correlate `signal` against the template
+    ///
+    /// Output length and indexing follow [`Mode`] exactly as in
+    /// [`fft_correlate_1d`](crate::fft_correlate_1d), including returning an empty vector for
+    /// empty inputs or Valid mode with a signal shorter than the template.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+    pub fn correlate(&mut self, signal: &[T], mode: Mode) -> Result<Vec<T>> {
+        if signal.is_empty() || self.template.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let output_len = signal.len() + self.template.len() - 1;
+        let fft_size = FftSizing::default().fft_len(output_len);
+        let (r2c, c2r) = plan_fft(fft_size);
+
+        let template_spectrum = match self.spectra.get(&fft_size) {
+            Some(spectrum) => spectrum,
+            None => {
+                let spectrum = reversed_template_spectrum(&self.template, fft_size, r2c.as_ref())?;
+                self.spectra.entry(fft_size).or_insert(spectrum)
+            }
+        };
+
+        correlate_with_template_spectrum(
+            signal,
+            self.template.len(),
+            template_spectrum,
+            fft_size,
+            r2c.as_ref(),
+            c2r.as_ref(),
+            mode,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    #[test]
+    fn test_template_correlator_matches_fft_correlate_1d() {
+        let template: Vec<f32> = (0..7).map(|i| ((i as f32) * 0.9).sin()).collect();
+        let mut correlator = TemplateCorrelator::new(&template);
+
+        for signal_len in [1, 3, 7, 20, 57, 200] {
+            let signal: Vec<f32> = (0..signal_len)
+                .map(|i| ((i as f32) * 0.3).cos() + (i as f32) * 0.01)
+                .collect();
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                let expected = fft_correlate_1d(&signal, &template, mode).unwrap();
+                let actual = correlator.correlate(&signal, mode).unwrap();
+                assert_eq!(actual, expected, "Mismatch for signal {} mode {:?}", signal_len, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_template_correlator_f64() {
+        let template: Vec<f64> = vec![0.25, -1.0, 0.5, 2.0];
+        let signal: Vec<f64> = (0..33).map(|i| (i as f64 * 0.37).sin()).collect();
+        let mut correlator = TemplateCorrelator::new(&template);
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            let expected = fft_correlate_1d(&signal, &template, mode).unwrap();
+            assert_eq!(correlator.correlate(&signal, mode).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_template_correlator_caches_per_fft_size() {
+        let mut correlator = TemplateCorrelator::new(&[1.0f32, 2.0, 3.0]);
+
+        correlator.correlate(&[1.0; 10], Mode::Full).unwrap();
+        correlator.correlate(&[2.0; 9], Mode::Full).unwrap();
+        assert_eq!(correlator.spectra.len(), 1, "Both signals share FFT size 12");
+
+        correlator.correlate(&[1.0; 40], Mode::Full).unwrap();
+        assert_eq!(correlator.spectra.len(), 2);
+    }
+
+    #[test]
+    fn test_template_correlator_empty_inputs() {
+        let mut correlator = TemplateCorrelator::<f32>::new(&[]);
+        assert!(correlator.is_empty());
+        assert!(correlator.correlate(&[1.0, 2.0], Mode::Full).unwrap().is_empty());
+
+        let mut correlator = TemplateCorrelator::new(&[1.0, 2.0]);
+        assert_eq!(correlator.len(), 2);
+        assert!(correlator.correlate(&[], Mode::Same).unwrap().is_empty());
+        assert!(correlator.correlate(&[1.0], Mode::Valid).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! Strict input validation
+//!
+//! The correlation functions are lenient: empty inputs, or a template longer than the signal in
+//! Valid mode, produce an empty output, and non-finite samples propagate into the result.
+//! [`validate_inputs`] turns each of these situations into a typed [`FftCorrelationError`] so
+//! callers can tell "nothing to compute" apart from a bug upstream, and
+//! [`fft_correlate_1d_strict`] applies it before correlating.
+
+use std::mem::size_of;
+
+use crate::{fft_correlate_1d, Complex, FftCorrelationError, FftFloat, InputKind, Mode, Result};
+
+/// Check that `signal` and `template` can be correlated in `mode` without degenerate output
+///
+/// Checks run in this order, and the first failure is returned:
+///
+/// 1. `FftCorrelationError::EmptySignal` / `FftCorrelationError::EmptyTemplate`
+/// 2. `FftCorrelationError::TemplateLongerThanSignal` in Valid mode
+/// 3. `FftCorrelationError::SizeOverflow` if the padded FFT buffers could not be addressed
+/// 4. `FftCorrelationError::NonFinite` with the first NaN or infinite sample, signal first
+///
+/// # Errors
+///
+/// Returns the first failed check as described above.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{validate_inputs, FftCorrelationError, InputKind, Mode};
+///
+/// let err = validate_inputs(&[1.0f32, f32::NAN], &[1.0], Mode::Full).unwrap_err();
+/// assert!(matches!(err, FftCorrelationError::NonFinite { input: InputKind::Signal, index: 1 }));
+/// assert!(validate_inputs(&[1.0f32, 2.0], &[1.0], Mode::Valid).is_ok());
+/// ```
+pub fn validate_inputs<T: FftFloat>(signal: &[T], template: &[T], mode: Mode) -> Result<()> {
+    if signal.is_empty() {
+        return Err(FftCorrelationError::EmptySignal);
+    }
+    if template.is_empty() {
+        return Err(FftCorrelationError::EmptyTemplate);
+    }
+    if mode == Mode::Valid && template.len() > signal.len() {
+        return Err(FftCorrelationError::TemplateLongerThanSignal {
+            signal_len: signal.len(),
+            template_len: template.len(),
+        });
+    }
+
+    check_size::<T>(signal.len(), template.len())?;
+
+    check_finite(signal, template)
+}
+
+// First NaN or infinite sample, searching the signal before the template
+pub(crate) fn check_finite<T: FftFloat>(signal: &[T], template: &[T]) -> Result<()> {
+    for (input, data) in [(InputKind::Signal, signal), (InputKind::Template, template)] {
+        if let Some(index) = data.iter().position(|v| !v.is_finite()) {
+            return Err(FftCorrelationError::NonFinite { input, index });
+        }
+    }
+    Ok(())
+}
+
+// The FFT length may round the Full length up to twice its size, and the spectrum holds complex
+// samples; both must stay within isize::MAX bytes
+fn check_size<T: FftFloat>(signal_len: usize, template_len: usize) -> Result<()> {
+    let max_full_len = isize::MAX as usize / (2 * size_of::<Complex<T>>());
+    let full_len = signal_len.checked_add(template_len - 1);
+    if full_len.is_none_or(|len| len > max_full_len) {
+        return Err(FftCorrelationError::SizeOverflow { signal_len, template_len });
+    }
+    Ok(())
+}
+
+/// Correlate two 1D signals using FFT, rejecting degenerate inputs
+///
+/// Same as [`fft_correlate_1d`], but runs [`validate_inputs`] first, so the output is never
+/// empty and never contains NaN or infinity caused by the inputs.
+///
+/// # Errors
+///
+/// Returns the validation errors listed on [`validate_inputs`], and
+/// `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d, fft_correlate_1d_strict, FftCorrelationError, Mode};
+///
+/// let (signal, template) = ([1.0f64, 2.0], [1.0, 1.0, 1.0]);
+/// assert!(fft_correlate_1d(&signal, &template, Mode::Valid).unwrap().is_empty());
+/// let err = fft_correlate_1d_strict(&signal, &template, Mode::Valid).unwrap_err();
+/// assert!(matches!(err, FftCorrelationError::TemplateLongerThanSignal { signal_len: 2, template_len: 3 }));
+/// ```
+pub fn fft_correlate_1d_strict<T: FftFloat>(signal: &[T], template: &[T], mode: Mode) -> Result<Vec<T>> {
+    validate_inputs(signal, template, mode)?;
+    fft_correlate_1d(signal, template, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_matches_lenient_on_valid_input() {
+        let signal: Vec<f64> = (0..50).map(|i| (i as f64 * 0.3).sin()).collect();
+        let template = [0.5, -1.0, 2.0];
+        for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+            assert_eq!(
+                fft_correlate_1d_strict(&signal, &template, mode).unwrap(),
+                fft_correlate_1d(&signal, &template, mode).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_degenerate_lengths_are_typed_errors() {
+        let one = [1.0f32];
+        assert!(matches!(validate_inputs::<f32>(&[], &[], Mode::Full), Err(FftCorrelationError::EmptySignal)));
+        assert!(matches!(validate_inputs::<f32>(&one, &[], Mode::Full), Err(FftCorrelationError::EmptyTemplate)));
+        assert!(matches!(
+            validate_inputs(&one, &[1.0; 4], Mode::Valid),
+            Err(FftCorrelationError::TemplateLongerThanSignal { signal_len: 1, template_len: 4 })
+        ));
+        // Full and Same are well defined for a longer template
+        assert!(validate_inputs(&one, &[1.0; 4], Mode::Full).is_ok());
+        assert!(validate_inputs(&one, &[1.0; 4], Mode::Same).is_ok());
+    }
+
+    #[test]
+    fn test_non_finite_reports_first_offender() {
+        let signal = [0.0f64, 1.0, f64::INFINITY, f64::NAN];
+        let template = [f64::NEG_INFINITY, 1.0];
+        let err = fft_correlate_1d_strict(&signal, &template, Mode::Same).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::NonFinite { input: InputKind::Signal, index: 2 }));
+        let err = validate_inputs(&signal[..2], &template, Mode::Full).unwrap_err();
+        assert!(matches!(err, FftCorrelationError::NonFinite { input: InputKind::Template, index: 0 }));
+        assert_eq!(err.to_string(), "template sample 0 is not finite");
+    }
+
+    #[test]
+    fn test_size_overflow() {
+        assert!(check_size::<f64>(1 << 20, 1 << 20).is_ok());
+        assert!(matches!(
+            check_size::<f32>(usize::MAX, 2),
+            Err(FftCorrelationError::SizeOverflow { signal_len: usize::MAX, template_len: 2 })
+        ));
+        // No overflow in the addition, but the padded spectrum would not fit in memory
+        assert!(check_size::<f64>(usize::MAX / 16, usize::MAX / 16).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Allocation-free correlation into caller-provided buffers
+//!
+//! [`fft_correlate_1d`](crate::fft_correlate_1d) allocates padded inputs, two spectra, FFT scratch
+//! and the output on every call. [`CorrelationWorkspace`] owns all of those buffers and
+//! [`fft_correlate_1d_into`] reuses them, so once the workspace has seen the largest input size
+//! (or was created for it) correlation performs no heap allocations. Smaller inputs run on an FFT
+//! length the workspace has already planned rather than planning a new one. FFTs run with
+//! realfft's `process_with_scratch` on the workspace's scratch buffer.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealToComplex};
+use std::sync::Arc;
+
+use crate::{plan_fft, FftCorrelationError, FftFloat, FftSizing, Mode, Result};
+
+/// Forward and inverse plans of one FFT length
+type Plans<T> = (Arc<dyn RealToComplex<T>>, Arc<dyn ComplexToReal<T>>);
+
+// Plans of one FFT length and the Full correlation length they were made for
+struct PlannedLength<T: FftFloat> {
+    fft_size: usize,
+    full_len: usize,
+    plans: Plans<T>,
+}
+
+/// Reusable buffers and FFT plans for [`fft_correlate_1d_into`]
+///
+/// Buffers grow on demand and never shrink, so the first call at a new largest size allocates
+/// and every later call at or below that size does not. Create the workspace with
+/// [`with_capacity`](Self::with_capacity) to move that warm-up out of a real-time loop.
+///
+/// The workspace keeps the plans of every FFT length it has been prepared for. An input whose
+/// own FFT length was never planned uses the shortest planned length that fits it, which gives
+/// the same values up to round-off; [`reserve`](Self::reserve) the smaller sizes as well to run
+/// them at their own length. The workspace owns its plans, so it stays warm when moved to another
+/// thread.
+pub struct CorrelationWorkspace<T: FftFloat> {
+    sizing: FftSizing,
+    // Planned FFT lengths in ascending order
+    plans: Vec<PlannedLength<T>>,
+    time: Vec<T>,
+    signal_spectrum: Vec<Complex<T>>,
+    template_spectrum: Vec<Complex<T>>,
+    scratch: Vec<Complex<T>>,
+}
+
+impl<T: FftFloat> CorrelationWorkspace<T> {
+    /// Create an empty workspace; buffers are allocated by the first correlation
+    pub fn new() -> Self {
+        Self {
+            sizing: FftSizing::default(),
+            plans: Vec::new(),
+            time: Vec::new(),
+            signal_spectrum: Vec::new(),
+            template_spectrum: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Create a workspace with buffers and plans ready for inputs up to the given lengths
+    pub fn with_capacity(max_signal_len: usize, max_template_len: usize) -> Self {
+        let mut workspace = Self::new();
+        workspace.reserve(max_signal_len, max_template_len);
+        workspace
+    }
+
+    /// Use `sizing` to choose FFT lengths instead of the default [`FftSizing::Fast`]
+    ///
+    /// Lengths planned so far, for example by [`with_capacity`](Self::with_capacity), are planned
+    /// again under `sizing`, so the order of the two calls does not matter.
+    pub fn with_sizing(mut self, sizing: FftSizing) -> Self {
+        self.sizing = sizing;
+        for PlannedLength { full_len, .. } in std::mem::take(&mut self.plans) {
+            self.plan(full_len);
+        }
+        self.grow_buffers();
+        self
+    }
+
+    /// Grow buffers so inputs up to the given lengths need no further allocation
+    ///
+    /// Also plans the FFT length of exactly that size, so inputs of those lengths run at their own
+    /// FFT length and smaller ones can fall back to it.
+    pub fn reserve(&mut self, max_signal_len: usize, max_template_len: usize) {
+        if max_signal_len == 0 || max_template_len == 0 {
+            return;
+        }
+        self.plan(max_signal_len + max_template_len - 1);
+        self.grow_buffers();
+    }
+
+    /// Longest FFT length the workspace is planned for, or 0 before first use
+    pub fn fft_size(&self) -> usize {
+        self.plans.last().map_or(0, |planned| planned.fft_size)
+    }
+
+    /// FFT lengths the workspace is planned for, in ascending order
+    pub fn planned_fft_sizes(&self) -> Vec<usize> {
+        self.plans.iter().map(|planned| planned.fft_size).collect()
+    }
+
+    // Plan the FFT length the sizing policy picks for `full_len` unless it is already planned
+    fn plan(&mut self, full_len: usize) {
+        let fft_size = self.sizing.fft_len(full_len);
+        match self.plans.binary_search_by_key(&fft_size, |planned| planned.fft_size) {
+            Ok(index) => self.plans[index].full_len = self.plans[index].full_len.max(full_len),
+            Err(index) => self.plans.insert(index, PlannedLength { fft_size, full_len, plans: plan_fft(fft_size) }),
+        }
+    }
+
+    // Shortest planned length that holds a Full correlation of `full_len` samples, with its
+    // plans; plans the sizing policy's length only if none fits
+    fn prepare(&mut self, full_len: usize) -> (usize, Plans<T>) {
+        let index = self.plans.partition_point(|planned| planned.fft_size < full_len);
+        if index == self.plans.len() {
+            self.plan(full_len);
+            self.grow_buffers();
+        }
+        let planned = &self.plans[index];
+        (planned.fft_size, planned.plans.clone())
+    }
+
+    // Size every buffer for the longest planned FFT length
+    fn grow_buffers(&mut self) {
+        let fft_size = self.fft_size();
+        let spectrum_len = fft_size / 2 + 1;
+        let scratch_len = self
+            .plans
+            .iter()
+            .map(|PlannedLength { plans: (r2c, c2r), .. }| r2c.get_scratch_len().max(c2r.get_scratch_len()))
+            .max()
+            .unwrap_or(0);
+        self.time.resize(fft_size, T::zero());
+        self.signal_spectrum.resize(spectrum_len, Complex::new(T::zero(), T::zero()));
+        self.template_spectrum.resize(spectrum_len, Complex::new(T::zero(), T::zero()));
+        self.scratch.resize(scratch_len, Complex::new(T::zero(), T::zero()));
+    }
+}
+
+impl<T: FftFloat> Default for CorrelationWorkspace<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FftFloat> std::fmt::Debug for CorrelationWorkspace<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CorrelationWorkspace")
+            .field("sizing", &self.sizing)
+            .field("fft_size", &self.fft_size())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Correlate two 1D signals using FFT, writing into `out` and reusing `workspace`
+///
+/// Produces the same values as [`fft_correlate_1d`](crate::fft_correlate_1d) (with the
+/// workspace's sizing policy) up to round-off. `out` is cleared and filled with
+/// `mode.output_len(N, M)` samples; its capacity is reused, so once the workspace is warmed up
+/// for inputs at least this large neither the workspace nor `out` allocates.
+///
+/// Empty inputs, or Valid mode with a signal shorter than the template, leave `out` empty.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::FftProcessing` if FFT processing fails.
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{fft_correlate_1d_into, CorrelationWorkspace, Mode};
+///
+/// let mut workspace = CorrelationWorkspace::with_capacity(1024, 16);
+/// let mut out = Vec::with_capacity(1024);
+/// let template = [0.5f32, 1.0, 0.5];
+/// for frame in [[1.0f32; 1024], [0.0; 1024]] {
+///     fft_correlate_1d_into(&mut workspace, &frame, &template, Mode::Same, &mut out).unwrap();
+///     assert_eq!(out.len(), 1024);
+/// }
+/// ```
+pub fn fft_correlate_1d_into<T: FftFloat>(
+    workspace: &mut CorrelationWorkspace<T>,
+    signal: &[T],
+    template: &[T],
+    mode: Mode,
+    out: &mut Vec<T>,
+) -> Result<()> {
+    out.clear();
+    let len = mode.output_len(signal.len(), template.len());
+    if len == 0 {
+        return Ok(());
+    }
+
+    let full_len = signal.len() + template.len() - 1;
+    let (fft_size, (r2c, c2r)) = workspace.prepare(full_len);
+    let time = &mut workspace.time[..fft_size];
+    let signal_spectrum = &mut workspace.signal_spectrum[..fft_size / 2 + 1];
+    let template_spectrum = &mut workspace.template_spectrum[..fft_size / 2 + 1];
+    let scratch = &mut workspace.scratch;
+
+    // Reversed, zero-padded template
+    time.fill(T::zero());
+    for (dst, &val) in time.iter_mut().zip(template.iter().rev()) {
+        *dst = val;
+    }
+    r2c.process_with_scratch(time, template_spectrum, scratch)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for template: {:?}", e)))?;
+
+    // Zero-padded signal; process_with_scratch may have overwritten the whole buffer
+    time.fill(T::zero());
+    time[..signal.len()].copy_from_slice(signal);
+    r2c.process_with_scratch(time, signal_spectrum, scratch)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT forward process failed for signal: {:?}", e)))?;
+
+    for (s, t) in signal_spectrum.iter_mut().zip(template_spectrum.iter()) {
+        *s *= t;
+    }
+    c2r.process_with_scratch(signal_spectrum, time, scratch)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+
+    let normalization = T::from_len(fft_size);
+    let start = mode.full_offset(template.len());
+    out.extend(time[start..start + len].iter().map(|&x| x / normalization));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_correlate_1d;
+
+    #[test]
+    fn test_into_matches_fft_correlate_1d() {
+        let mut workspace = CorrelationWorkspace::new();
+        let mut out = Vec::new();
+        // Varying sizes, shrinking and growing, exercise plan selection and buffer reuse
+        for (signal_len, template_len) in [(100, 7), (1000, 64), (33, 33), (10, 20), (1, 1), (257, 3)] {
+            let signal: Vec<f64> = (0..signal_len).map(|i| ((i as f64) * 0.37).sin()).collect();
+            let template: Vec<f64> = (0..template_len).map(|i| ((i as f64) * 0.91).cos()).collect();
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                fft_correlate_1d_into(&mut workspace, &signal, &template, mode, &mut out).unwrap();
+                let expected = fft_correlate_1d(&signal, &template, mode).unwrap();
+                assert_eq!(out.len(), expected.len());
+                for (a, b) in out.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-12, "{} x {} {:?}", signal_len, template_len, mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_smaller_inputs_reuse_planned_lengths() {
+        let mut workspace = CorrelationWorkspace::<f64>::with_capacity(4000, 100);
+        let largest = workspace.fft_size();
+        assert_eq!(workspace.planned_fft_sizes(), vec![largest]);
+
+        // A smaller input falls back to the planned length instead of planning its own
+        let signal: Vec<f64> = (0..2000).map(|i| ((i as f64) * 0.01).sin()).collect();
+        let template: Vec<f64> = (0..100).map(|i| ((i as f64) * 0.3).cos()).collect();
+        let mut out = Vec::new();
+        fft_correlate_1d_into(&mut workspace, &signal, &template, Mode::Full, &mut out).unwrap();
+        assert_eq!(workspace.planned_fft_sizes(), vec![largest]);
+        let expected = fft_correlate_1d(&signal, &template, Mode::Full).unwrap();
+        for (a, b) in out.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-10);
+        }
+
+        // Reserving the smaller size plans its own length, which later calls prefer
+        workspace.reserve(2000, 100);
+        let own = FftSizing::default().fft_len(2099);
+        assert_eq!(workspace.planned_fft_sizes(), vec![own, largest]);
+        fft_correlate_1d_into(&mut workspace, &signal, &template, Mode::Full, &mut out).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_sizing_applies_in_either_order() {
+        let after = CorrelationWorkspace::<f32>::with_capacity(4000, 100).with_sizing(FftSizing::PowerOfTwo);
+        let mut before = CorrelationWorkspace::<f32>::new().with_sizing(FftSizing::PowerOfTwo);
+        before.reserve(4000, 100);
+        assert_eq!(after.planned_fft_sizes(), vec![8192]);
+        assert_eq!(before.planned_fft_sizes(), vec![8192]);
+
+        // Every planned length is planned again under the new policy
+        let mut workspace = CorrelationWorkspace::<f32>::with_capacity(3000, 100);
+        workspace.reserve(4000, 100);
+        assert_eq!(workspace.planned_fft_sizes().len(), 2);
+        let workspace = workspace.with_sizing(FftSizing::PowerOfTwo);
+        assert_eq!(workspace.planned_fft_sizes(), vec![4096, 8192]);
+    }
+
+    #[test]
+    fn test_warm_workspace_moves_between_threads() {
+        let mut workspace = CorrelationWorkspace::<f64>::with_capacity(500, 20);
+        let signal: Vec<f64> = (0..500).map(|i| ((i as f64) * 0.2).sin()).collect();
+        let template = signal[100..120].to_vec();
+        let expected = fft_correlate_1d(&signal, &template, Mode::Valid).unwrap();
+        let (workspace, out) = std::thread::spawn(move || {
+            let mut out = Vec::new();
+            fft_correlate_1d_into(&mut workspace, &signal, &template, Mode::Valid, &mut out).unwrap();
+            (workspace, out)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(workspace.planned_fft_sizes().len(), 1);
+        for (a, b) in out.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_into_empty_inputs_clear_output() {
+        let mut workspace = CorrelationWorkspace::<f32>::new();
+        let mut out = vec![1.0f32; 4];
+        fft_correlate_1d_into(&mut workspace, &[], &[1.0], Mode::Full, &mut out).unwrap();
+        assert!(out.is_empty());
+        fft_correlate_1d_into(&mut workspace, &[1.0, 2.0], &[1.0, 2.0, 3.0], Mode::Valid, &mut out).unwrap();
+        assert!(out.is_empty());
+        assert_eq!(workspace.fft_size(), 0);
+    }
+}
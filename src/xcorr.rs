@@ -0,0 +1,197 @@
+//! MATLAB-style cross-correlation over a symmetric lag window
+//!
+//! [`xcorr`] mirrors MATLAB's `[r, lags] = xcorr(x, y, maxlag, scaleopt)`: it returns the
+//! correlation at lags `-maxlag..=maxlag` together with the lag vector. Lag `m` is
+//! `sum_n x[n + m] * y[n]`, the same convention as [`Mode::lag_at`](crate::Mode::lag_at) with `x` as
+//! the signal and `y` as the template.
+//!
+//! Only the requested window is kept free of circular wrap-around, so the FFT needs
+//! `max(len_x, len_y) + maxlag` points instead of the `len_x + len_y - 1` of a Full correlation.
+
+use crate::correlate2d::zero_real_bins;
+use crate::lag_window::{lag_buffer, max_lag_bound};
+use crate::{padded_spectrum, plan_fft, FftCorrelationError, FftFloat, FftSizing, Result, Scaling};
+
+/// Cross-correlate `x` and `y` at lags `-max_lag..=max_lag`, MATLAB `xcorr` style
+///
+/// Returns `(values, lags)` with `2 * max_lag + 1` entries each; `max_lag` defaults to
+/// `N - 1` where `N = max(x.len(), y.len())`. Lags with no overlapping samples are zero.
+///
+/// Scaling follows MATLAB, treating the shorter input as zero-padded to `N`:
+///
+/// - [`Scaling::None`]: raw sums
+/// - [`Scaling::Biased`]: divided by `N`
+/// - [`Scaling::Unbiased`]: divided by `N - |m|` (zero for `|m| >= N`)
+/// - [`Scaling::Coeff`]: divided by `sqrt(sum(x²) · sum(y²))`, so `xcorr(x, x)` is 1 at lag 0;
+///   zero if either input is all zeros
+///
+/// Returns empty vectors if either input is empty.
+///
+/// # Errors
+///
+/// Returns `FftCorrelationError::InvalidLagRange` if `max_lag` exceeds `isize::MAX` or the
+/// `2 * max_lag + 1` values cannot be allocated, and `FftCorrelationError::FftProcessing` if FFT
+/// processing fails.
+///
+/// # References
+///
+/// - MATLAB xcorr: https://www.mathworks.com/help/signal/ref/xcorr.html
+///
+/// # Example
+///
+/// ```
+/// use fft_correlation::{xcorr, Scaling};
+///
+/// // y is x delayed by two samples, so x leads: the peak is at lag -2
+/// let x = [0.0f64, 1.0, 3.0, 1.0, 0.0, 0.0, 0.0];
+/// let y = [0.0f64, 0.0, 0.0, 1.0, 3.0, 1.0, 0.0];
+/// let (r, lags) = xcorr(&x, &y, Some(3), Scaling::None).unwrap();
+/// assert_eq!(lags, vec![-3, -2, -1, 0, 1, 2, 3]);
+/// let best = (0..r.len()).max_by(|&a, &b| r[a].partial_cmp(&r[b]).unwrap()).unwrap();
+/// assert_eq!(lags[best], -2);
+/// ```
+pub fn xcorr<T: FftFloat>(x: &[T], y: &[T], max_lag: Option<usize>, scaling: Scaling) -> Result<(Vec<T>, Vec<isize>)> {
+    if x.is_empty() || y.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let n = x.len().max(y.len());
+    let max_lag = max_lag.unwrap_or(n - 1);
+    let bound = max_lag_bound(max_lag)?;
+    let mut lags = lag_buffer(-bound, bound)?;
+    lags.extend(-bound..=bound);
+    let mut values = lag_buffer(-bound, bound)?;
+
+    // Lags that can be non-zero: template y[0] aligned with x[m] for -(len_y - 1) <= m <= len_x - 1
+    let (lowest, highest) = (-(y.len() as isize - 1), x.len() as isize - 1);
+    let window = max_lag.min(n - 1);
+    let fft_size = FftSizing::default().fft_len((n + window).min(x.len() + y.len() - 1));
+    let (r2c, c2r) = plan_fft(fft_size);
+
+    let mut cross = padded_spectrum(x, fft_size, r2c.as_ref())?;
+    let y_spectrum = padded_spectrum(y, fft_size, r2c.as_ref())?;
+    for (a, b) in cross.iter_mut().zip(y_spectrum.iter()) {
+        *a *= b.conj();
+    }
+    zero_real_bins(&mut cross, fft_size);
+    let mut circular = c2r.make_output_vec();
+    c2r.process(&mut cross, &mut circular)
+        .map_err(|e| FftCorrelationError::FftProcessing(format!("FFT inverse process failed: {:?}", e)))?;
+
+    let normalization = T::from_len(fft_size);
+    let coeff = match scaling {
+        Scaling::Coeff => {
+            let energy = |v: &[T]| v.iter().fold(T::zero(), |acc, &s| acc + s * s);
+            (energy(x) * energy(y)).sqrt()
+        }
+        _ => T::one(),
+    };
+    values.extend(lags.iter().map(|&lag| {
+        if lag < lowest || lag > highest {
+            return T::zero();
+        }
+        let value = circular[lag.rem_euclid(fft_size as isize) as usize] / normalization;
+        match scaling {
+            Scaling::None => value,
+            Scaling::Biased => value / T::from_len(n),
+            Scaling::Unbiased => value / T::from_len(n - lag.unsigned_abs()),
+            Scaling::Coeff if coeff > T::zero() => value / coeff,
+            Scaling::Coeff => T::zero(),
+        }
+    }));
+    Ok((values, lags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{autocorrelate, AutocorrOptions};
+
+    // MATLAB definition with the shorter input zero-padded
+    fn naive_xcorr(x: &[f64], y: &[f64], max_lag: usize) -> Vec<f64> {
+        (-(max_lag as isize)..=max_lag as isize)
+            .map(|m| {
+                (0..y.len())
+                    .filter_map(|i| {
+                        let j = i as isize + m;
+                        (j >= 0 && (j as usize) < x.len()).then(|| x[j as usize] * y[i])
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn test_signal(len: usize, seed: f64) -> Vec<f64> {
+        (0..len).map(|i| ((i as f64) * seed).sin() + 0.2 * ((i as f64) * seed * 3.1).cos()).collect()
+    }
+
+    #[test]
+    fn test_xcorr_matches_naive_for_all_windows() {
+        for (len_x, len_y) in [(1, 1), (5, 5), (12, 7), (7, 12), (30, 30), (64, 3)] {
+            let x = test_signal(len_x, 0.37);
+            let y = test_signal(len_y, 0.83);
+            let n = len_x.max(len_y);
+            for max_lag in [0, 1, 3, n - 1, n + 4] {
+                let (r, lags) = xcorr(&x, &y, Some(max_lag), Scaling::None).unwrap();
+                let expected = naive_xcorr(&x, &y, max_lag);
+                assert_eq!(lags.len(), 2 * max_lag + 1);
+                for (a, b) in r.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-10, "{} x {} maxlag {}", len_x, len_y, max_lag);
+                }
+            }
+            let (r, lags) = xcorr(&x, &y, None, Scaling::None).unwrap();
+            assert_eq!(lags.first(), Some(&-(n as isize - 1)));
+            assert_eq!(r.len(), 2 * n - 1);
+        }
+    }
+
+    #[test]
+    fn test_xcorr_scalings_match_matlab_definitions() {
+        let x = test_signal(20, 0.5);
+        let y = test_signal(20, 0.9);
+        let (raw, lags) = xcorr(&x, &y, Some(6), Scaling::None).unwrap();
+        let (biased, _) = xcorr(&x, &y, Some(6), Scaling::Biased).unwrap();
+        let (unbiased, _) = xcorr(&x, &y, Some(6), Scaling::Unbiased).unwrap();
+        let (coeff, _) = xcorr(&x, &y, Some(6), Scaling::Coeff).unwrap();
+        let norm = (x.iter().map(|v| v * v).sum::<f64>() * y.iter().map(|v| v * v).sum::<f64>()).sqrt();
+        for (k, &lag) in lags.iter().enumerate() {
+            assert!((biased[k] - raw[k] / 20.0).abs() < 1e-12);
+            assert!((unbiased[k] - raw[k] / (20 - lag.unsigned_abs()) as f64).abs() < 1e-12);
+            assert!((coeff[k] - raw[k] / norm).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_xcorr_of_signal_with_itself_is_autocorrelation() {
+        let x = test_signal(50, 0.21);
+        for scaling in [Scaling::None, Scaling::Biased, Scaling::Unbiased, Scaling::Coeff] {
+            let (r, _) = xcorr(&x, &x, Some(10), scaling).unwrap();
+            let acf = autocorrelate(&x, &AutocorrOptions { max_lag: Some(10), scaling, one_sided: false }).unwrap();
+            for (a, b) in r.iter().zip(acf.iter()) {
+                assert!((a - b).abs() < 1e-10, "{:?}", scaling);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xcorr_degenerate_inputs() {
+        let (r, lags) = xcorr::<f32>(&[], &[1.0], None, Scaling::Biased).unwrap();
+        assert!(r.is_empty() && lags.is_empty());
+        let (r, _) = xcorr(&[0.0f32; 4], &[1.0; 4], Some(2), Scaling::Coeff).unwrap();
+        assert_eq!(r, vec![0.0; 5]);
+        // Unbiased lags beyond the overlap are zero rather than divided by zero
+        let (r, _) = xcorr(&[1.0f64, 2.0], &[3.0], Some(3), Scaling::Unbiased).unwrap();
+        assert!(r.iter().all(|v| v.is_finite()));
+        assert!((r[3] - 1.5).abs() < 1e-12 && (r[4] - 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_xcorr_rejects_unallocatable_max_lag() {
+        let x = [1.0f64, 2.0, 3.0];
+        for max_lag in [usize::MAX, isize::MAX as usize, usize::MAX / 4] {
+            assert!(matches!(
+                xcorr(&x, &x, Some(max_lag), Scaling::None),
+                Err(FftCorrelationError::InvalidLagRange { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Allocation counts for `fft_correlate_1d_into`
+//!
+//! Lives in its own test binary because counting needs a `#[global_allocator]`, which would
+//! otherwise wrap every allocation of the library's unit tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use fft_correlation::{fft_correlate_1d_into, CorrelationWorkspace, FftSizing, Mode};
+
+// Counts allocations made by the current thread so parallel tests do not interfere
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+fn test_vectors(signal_len: usize, template_len: usize) -> (Vec<f32>, Vec<f32>) {
+    let signal = (0..signal_len).map(|i| ((i as f32) * 0.01).sin()).collect();
+    let template = (0..template_len).map(|i| ((i as f32) * 0.3).cos()).collect();
+    (signal, template)
+}
+
+#[test]
+fn test_into_performs_no_allocations_after_warm_up() {
+    let (signal, template) = test_vectors(4000, 100);
+    let mut workspace = CorrelationWorkspace::with_capacity(signal.len(), template.len());
+    let mut out = Vec::with_capacity(signal.len() + template.len());
+
+    let before = allocations();
+    for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+        fft_correlate_1d_into(&mut workspace, &signal, &template, mode, &mut out).unwrap();
+    }
+    assert_eq!(allocations(), before, "Correlation allocated after warm-up");
+}
+
+#[test]
+fn test_smaller_inputs_perform_no_allocations_after_warm_up() {
+    for (sizing, planned) in [(FftSizing::Fast, FftSizing::Fast.fft_len(4099)), (FftSizing::PowerOfTwo, 8192)] {
+        let mut workspace = CorrelationWorkspace::with_capacity(4000, 100).with_sizing(sizing);
+        assert_eq!(workspace.planned_fft_sizes(), vec![planned]);
+        let mut out = Vec::with_capacity(4100);
+        let inputs: Vec<(Vec<f32>, Vec<f32>)> =
+            [(2000, 100), (4000, 7), (1, 1), (100, 100), (3999, 100)].iter().map(|&(n, m)| test_vectors(n, m)).collect();
+
+        let before = allocations();
+        for (signal, template) in &inputs {
+            for mode in [Mode::Full, Mode::Same, Mode::Valid] {
+                fft_correlate_1d_into(&mut workspace, signal, template, mode, &mut out).unwrap();
+            }
+        }
+        assert_eq!(allocations(), before, "{:?}: smaller input allocated after warm-up", sizing);
+        assert_eq!(workspace.planned_fft_sizes(), vec![planned]);
+    }
+}